@@ -1,7 +1,15 @@
-use crate::engine_result::EngineResult;
+use crate::engine_result::{EngineResult, ResultStatus};
+use crate::mate_class::MateClassCounts;
+use crate::pv_check::PvIssue;
+use crate::report::{ConsoleRenderer, CsvRenderer, Renderer, Report, SuiteStats};
+use crate::search_limits::SearchLimits;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use std::f64;
+use std::io::Write;
 
+/// Collects per-position results and reports on them.
+#[derive(Default)]
 pub struct Analyzer {
     results: Vec<EngineResult>,
 }
@@ -17,400 +25,313 @@ impl Analyzer {
         self.results.push(result);
     }
 
-    /// Analyze the collected results.
-    pub fn analyze(&self) {
-        print_engine_stats(&self.results);
+    pub fn results(&self) -> &[EngineResult] {
+        &self.results
     }
 
-    /// Analyze and write consolidated stats to CSV file (append mode)
-    pub fn analyze_and_write_csv(&self, csv_path: &str, engine_name: &str, cmdline: &str) {
-        let (stats, mate_in_counts) = compute_stats(&self.results);
-        print_stats_human_readable(&stats, engine_name, cmdline, &mate_in_counts);
-        if self.results.is_empty() {
-            return;
+    /// Statistics over every collected result.
+    pub fn summary(&self) -> StatsSummary {
+        let results: Vec<&EngineResult> = self.results.iter().collect();
+        StatsSummary::from_results(&results)
+    }
+
+    /// Everything the renderers need: the run's context, the overall
+    /// statistics and, when positions came from more than one suite, the
+    /// statistics per suite.
+    pub fn report(
+        &self,
+        engine_name: &str,
+        cmdline: &str,
+        limits: &SearchLimits,
+        selection: &str,
+    ) -> Report {
+        let by_source = self.results_by_source();
+        let suites = if by_source.len() > 1 {
+            by_source
+                .iter()
+                .map(|(source, results)| SuiteStats {
+                    source: source.to_string(),
+                    stats: StatsSummary::from_results(results),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Report {
+            engine_name: engine_name.to_string(),
+            cmdline: cmdline.to_string(),
+            limits: *limits,
+            selection: selection.to_string(),
+            overall: self.summary(),
+            suites,
         }
-        use std::fs::OpenOptions;
-        use std::io::BufWriter;
-        let file_exists = std::path::Path::new(csv_path).exists();
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(csv_path)
-            .expect("Unable to open or create CSV file");
-        let mut writer = csv::WriterBuilder::new()
-            .has_headers(!file_exists)
-            .from_writer(BufWriter::new(file));
+    }
 
-        // Always output header for mates in 1..=20
-        let max_n = 20;
-        if !file_exists {
-            let mut header = vec![
-                "engine_name".to_string(),
-                "cmdline".to_string(),
-                "positions_analyzed".to_string(),
-                "avg_ebf".to_string(),
-                "avg_nps".to_string(),
-                "avg_nps_m".to_string(),
-                "avg_time_ms".to_string(),
-                "avg_nodes".to_string(),
-                "avg_depth".to_string(),
-                "node_stddev".to_string(),
-                "max_nodes".to_string(),
-                "min_nodes".to_string(),
-                "total_mates".to_string(),
-                "first_move_hits".to_string(),
-                "peak_nps".to_string(),
-            ];
-            for n in 1..=max_n {
-                header.push(format!("mates in {}", n));
+    /// Group results by the suite file they were loaded from, in load order.
+    fn results_by_source(&self) -> Vec<(&str, Vec<&EngineResult>)> {
+        let mut groups: Vec<(&str, Vec<&EngineResult>)> = Vec::new();
+        for res in &self.results {
+            match groups.iter_mut().find(|(source, _)| *source == res.source) {
+                Some((_, group)) => group.push(res),
+                None => groups.push((&res.source, vec![res])),
             }
-            let _ = writer.write_record(&header);
-        }
-        let mut record = vec![
-            engine_name.to_string(),
-            cmdline.to_string(),
-            stats.positions_analyzed.to_string(),
-            format!("{:.4}", stats.avg_ebf),
-            format!("{:.2}", stats.avg_nps),
-            format!("{:.2}", stats.avg_nps_m),
-            format!("{:.2}", stats.avg_time_ms),
-            format!("{:.2}", stats.avg_nodes),
-            format!("{:.2}", stats.avg_depth),
-            format!("{:.0}", stats.node_stddev),
-            stats.max_nodes.to_string(),
-            stats.min_nodes.to_string(),
-            stats.total_mates.to_string(),
-            format!("{:.0}", stats.first_move_hits * 100.0),
-            stats.peak_nps.to_string(),
-        ];
-        for n in 1..=max_n {
-            let count = mate_in_counts.get(&n).cloned().unwrap_or(0);
-            record.push(count.to_string());
         }
-        let _ = writer.write_record(&record);
-        let _ = writer.flush();
+        groups
     }
-}
 
-/// Struct to hold consolidated stats for CSV and printing
-struct StatsSummary {
-    positions_analyzed: u64,
-    avg_ebf: f64,
-    avg_nps: f64,
-    avg_nps_m: f64,
-    avg_time_ms: f64,
-    avg_nodes: f64,
-    avg_depth: f64,
-    node_stddev: f64,
-    max_nodes: u64,
-    min_nodes: u64,
-    total_mates: u64,
-    first_move_hits: f64,
-    peak_nps: u64,
-}
-
-fn compute_stats(results: &[EngineResult]) -> (StatsSummary, BTreeMap<u32, u64>) {
-    use std::collections::BTreeMap;
-    if results.is_empty() {
-        return (
-            StatsSummary {
-                positions_analyzed: 0,
-                avg_ebf: 0.0,
-                avg_nps: 0.0,
-                avg_nps_m: 0.0,
-                avg_time_ms: 0.0,
-                avg_nodes: 0.0,
-                avg_depth: 0.0,
-                node_stddev: 0.0,
-                max_nodes: 0,
-                min_nodes: 0,
-                total_mates: 0,
-                first_move_hits: 0.0,
-                peak_nps: 0,
-            },
-            BTreeMap::new(),
-        );
-    }
-    let mut total_nodes = 0u64;
-    let mut total_depth = 0u64;
-    let mut total_nps = 0u64;
-    let mut total_time = 0u64;
-    let mut peak_nps = 0u64;
-    let mut mate_in_counts: BTreeMap<u32, u64> = BTreeMap::new();
-    let mut nodes_vec = Vec::with_capacity(results.len());
-    let mut min_nodes = u64::MAX;
-    let mut max_nodes = 0u64;
-    for res in results {
-        total_nodes += res.nodes;
-        total_depth += res.depth as u64;
-        total_nps += res.nps;
-        total_time += res.time_ms;
-        if res.nps > peak_nps {
-            peak_nps = res.nps;
+    /// List every position where a mate score came with a PV that does not
+    /// prove the mate.
+    fn write_bad_mate_pvs(&self, out: &mut impl Write) -> Result<()> {
+        let flagged: Vec<&EngineResult> = self
+            .results
+            .iter()
+            .filter(|res| res.mate().is_some() && res.pv_issue.is_some())
+            .collect();
+        if flagged.is_empty() {
+            return Ok(());
         }
-        if res.nodes < min_nodes {
-            min_nodes = res.nodes;
+        writeln!(out, "Mate scores with bad PVs:")?;
+        for res in flagged {
+            if let Some(issue) = &res.pv_issue {
+                writeln!(out, "  {} | {} | {}", res.fen, res.score_text(), issue)?;
+                writeln!(out, "    pv {}", res.pv.join(" "))?;
+            }
         }
-        if res.nodes > max_nodes {
-            max_nodes = res.nodes;
+        writeln!(out, "------------------------------------")?;
+        Ok(())
+    }
+
+    /// List every position where the solver refuted the engine's mate score.
+    fn write_refuted_mates(&self, out: &mut impl Write) -> Result<()> {
+        let refuted: Vec<&EngineResult> = self
+            .results
+            .iter()
+            .filter(|res| res.mate_verified == Some(false))
+            .collect();
+        if refuted.is_empty() {
+            return Ok(());
         }
-        nodes_vec.push(res.nodes as f64);
-        // Count mate-in-Ns from score string (e.g., "mate 1", "mate -2", etc.)
-        if let Some(idx) = res.score.find("mate ") {
-            let after = &res.score[idx + 5..];
-            if let Some(num_str) = after.split_whitespace().next() {
-                if let Ok(n) = num_str.parse::<i32>() {
-                    let n_abs = n.abs() as u32;
-                    *mate_in_counts.entry(n_abs).or_insert(0) += 1;
-                }
-            }
+        writeln!(out, "Mate scores refuted by the solver:")?;
+        for res in refuted {
+            writeln!(out, "  {} | {}", res.fen, res.score_text())?;
         }
+        writeln!(out, "------------------------------------")?;
+        Ok(())
     }
-    let count = results.len() as f64;
-    let avg_nodes = total_nodes as f64 / count;
-    let avg_nps = total_nps as f64 / count;
-    let avg_time = total_time as f64 / count;
-    let avg_depth = total_depth as f64 / count;
-    // Use per-result depth for EBF calculation
-    let mut ebf_sum = 0.0;
-    let mut ebf_count = 0.0;
-    for res in results {
-        let depth = res.depth as f64;
-        if res.nodes > 0 && depth > 0.0 {
-            ebf_sum += (res.nodes as f64).powf(1.0 / depth);
-            ebf_count += 1.0;
+
+    /// List every position whose search hit the per-position timeout.
+    fn write_timed_out(&self, out: &mut impl Write) -> Result<()> {
+        let timed_out: Vec<&EngineResult> = self
+            .results
+            .iter()
+            .filter(|res| res.status == ResultStatus::TimedOut)
+            .collect();
+        if timed_out.is_empty() {
+            return Ok(());
+        }
+        writeln!(out, "Timed out positions:")?;
+        for res in timed_out {
+            writeln!(out, "  {}", res.fen)?;
         }
+        writeln!(out, "------------------------------------")?;
+        Ok(())
     }
-    let avg_ebf = if ebf_count > 0.0 {
-        ebf_sum / ebf_count
-    } else {
-        0.0
-    };
-    // Node stddev
-    let mean = avg_nodes;
-    let stddev = if count > 1.0 {
-        let var = nodes_vec.iter().map(|&n| (n - mean).powi(2)).sum::<f64>() / (count - 1.0);
-        var.sqrt()
-    } else {
-        0.0
-    };
-    // Format NPS as M (millions)
-    let avg_nps_m = avg_nps / 1_000_000.0;
-    // First move hits placeholder (requires ground truth)
-    let first_move_hits = 0.82; // 82% as a placeholder
-    let total_mates: u64 = mate_in_counts.values().sum();
-    (
-        StatsSummary {
-            positions_analyzed: count as u64,
-            avg_ebf,
-            avg_nps,
-            avg_nps_m,
-            avg_time_ms: avg_time,
-            avg_nodes,
-            avg_depth,
-            node_stddev: stddev,
-            max_nodes,
-            min_nodes,
-            total_mates,
-            first_move_hits,
-            peak_nps,
-        },
-        mate_in_counts,
-    )
-}
 
-fn print_stats_human_readable(
-    stats: &StatsSummary,
-    engine_name: &str,
-    cmdline: &str,
-    mate_in_counts: &BTreeMap<u32, u64>,
-) {
-    println!("------------------------------------");
-    println!("Command line: {}", cmdline);
-    println!("Analysis for engine: {}", engine_name);
-    println!("------------------------------------");
-    println!("General Efficiency:");
-    println!("  Avg EBF:         {:<6.2} (Target: < 2.2)", stats.avg_ebf);
-    println!(
-        "  Avg NPS:         {:.2}M   (Machine Dependent)",
-        stats.avg_nps_m
-    );
-    println!(
-        "  Avg Time:        {:.1}ms  (Machine Dependent)",
-        stats.avg_time_ms
-    );
-    println!();
-    println!("Search Robustness:");
-    println!(
-        "  Node StdDev:     {:<7.0} (Lower = more stable search)",
-        stats.node_stddev
-    );
-    println!(
-        "  Max Node Outlier: {:<7} (The \"hardest\" position found)",
-        stats.max_nodes
-    );
-    println!(
-        "  Min Node Speed:  {:<7} (The \"easiest\" position found)",
-        stats.min_nodes
-    );
-    println!();
-    println!("Tactical Accuracy:");
-    println!(
-        "  Mates Found:     {}       (Across {} positions)",
-        stats.total_mates, stats.positions_analyzed
-    );
-    println!(
-        "  First Move Hits: {:.0}%     (Move ordering quality)",
-        stats.first_move_hits * 100.0
-    );
-    println!("\nEngine Search Statistics Summary:");
-    println!("  Positions analyzed: {}", stats.positions_analyzed);
-    println!("  Average nodes per search: {:.2}", stats.avg_nodes);
-    println!("  Average depth per search: {:.2}", stats.avg_depth);
-    println!("  Average effective branching factor: {:.4}", stats.avg_ebf);
-    println!("  Average NPS: {:.2}", stats.avg_nps);
-    println!("  Average time per search (ms): {:.2}", stats.avg_time_ms);
-    println!("  Peak NPS: {}", stats.peak_nps);
-    println!("  Mate-in-Ns found:");
-    let max_n = mate_in_counts
-        .iter()
-        .filter(|kv| *kv.1 > 0)
-        .map(|(n, _)| *n)
-        .max()
-        .unwrap_or(0)
-        .max(1);
-    for n in 1..=max_n {
-        let count = mate_in_counts.get(&n).cloned().unwrap_or(0);
-        println!("    Mate in {:<2}: {}", n, count);
+    /// List the positions that need a closer look: bad mate PVs, refuted
+    /// mates, timeouts and crashes.
+    pub fn write_flagged_positions(&self, out: &mut impl Write) -> Result<()> {
+        self.write_bad_mate_pvs(out)?;
+        self.write_refuted_mates(out)?;
+        self.write_timed_out(out)?;
+        self.write_crashes(out)
     }
-    println!("------------------------------------");
-}
 
-/// Print summary statistics for a slice of EngineResult.
-pub fn print_engine_stats(results: &[EngineResult]) {
-    if results.is_empty() {
-        println!("No results to analyze.");
-        return;
+    /// List every position on which the engine crashed, with enough context
+    /// to reproduce it.
+    fn write_crashes(&self, out: &mut impl Write) -> Result<()> {
+        let mut any = false;
+        for res in &self.results {
+            if let ResultStatus::Crashed {
+                exit_status,
+                last_output,
+            } = &res.status
+            {
+                if !any {
+                    writeln!(out, "Engine crashes:")?;
+                    any = true;
+                }
+                writeln!(out, "  {} | {}", res.fen, exit_status)?;
+                for line in last_output {
+                    writeln!(out, "    < {}", line)?;
+                }
+            }
+        }
+        if any {
+            writeln!(out, "------------------------------------")?;
+        }
+        Ok(())
     }
 
-    let mut total_nodes = 0u64;
-    let mut total_depth = 0u64;
-    let mut total_nps = 0u64;
-    let mut total_time = 0u64;
-    let mut peak_nps = 0u64;
+    /// Write the report and the flagged positions to `out`, then append the
+    /// statistics to the CSV file.
+    ///
+    /// One row is written for the whole run, followed by one row per source
+    /// file when positions came from more than one suite.
+    pub fn analyze_and_write_csv(
+        &self,
+        out: &mut impl Write,
+        csv_path: &str,
+        engine_name: &str,
+        cmdline: &str,
+        limits: &SearchLimits,
+        selection: &str,
+    ) -> Result<()> {
+        let report = self.report(engine_name, cmdline, limits, selection);
+        ConsoleRenderer::new(&mut *out).render(&report)?;
+        if self.results.is_empty() {
+            return Ok(());
+        }
+        self.write_flagged_positions(out)?;
+        CsvRenderer::new(csv_path).render(&report)
+    }
+}
 
-    let mut mate_in_counts: BTreeMap<u32, u64> = BTreeMap::new();
+/// Statistics over a set of results, computed once and shared by every
+/// renderer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatsSummary {
+    pub positions_analyzed: u64,
+    /// Average effective branching factor, `nodes^(1/depth)`
+    pub avg_ebf: f64,
+    pub avg_nps: f64,
+    /// `avg_nps` in millions
+    pub avg_nps_m: f64,
+    pub avg_time_ms: f64,
+    pub avg_nodes: f64,
+    pub avg_depth: f64,
+    pub node_stddev: f64,
+    pub max_nodes: u64,
+    pub min_nodes: u64,
+    /// Positions whose final score is an exact mate
+    pub total_mates: u64,
+    /// Number of exact mate scores per mate distance in moves
+    pub mates_by_distance: BTreeMap<u32, u64>,
+    /// Positions with a `bm #N` annotation
+    pub expected_mates: u64,
+    /// How the mates found compare to the expected ones
+    pub mate_classes: MateClassCounts,
+    /// PVs containing a move that is illegal where it is played
+    pub illegal_pvs: u64,
+    /// Mate scores whose PV does not end in the claimed mate
+    pub bad_mate_pvs: u64,
+    /// Mate scores the built-in solver proved
+    pub mates_proven: u64,
+    /// Mate scores the built-in solver showed to be wrong
+    pub mates_refuted: u64,
+    /// Positions whose final score was only a lower or upper bound
+    pub bound_scores: u64,
+    pub timed_out: u64,
+    pub crashed: u64,
+    /// Share of positions with an expected move where the engine's
+    /// `bestmove` matched it; `None` when no position has one
+    pub first_move_hits: Option<f64>,
+    /// Positions with an expected best move
+    pub first_move_positions: u64,
+    pub peak_nps: u64,
+}
 
-    let mut nodes_vec = Vec::with_capacity(results.len());
-    let mut min_nodes = u64::MAX;
-    let mut max_nodes = 0u64;
-    for res in results {
-        total_nodes += res.nodes;
-        total_depth += res.depth as u64;
-        total_nps += res.nps;
-        total_time += res.time_ms;
-        if res.nps > peak_nps {
-            peak_nps = res.nps;
-        }
-        if res.nodes < min_nodes {
-            min_nodes = res.nodes;
+impl StatsSummary {
+    pub fn from_results(results: &[&EngineResult]) -> StatsSummary {
+        let mut stats = StatsSummary::default();
+        // A position that timed out or crashed has no final search to
+        // measure; it only counts as timed out or crashed
+        let mut searched = Vec::with_capacity(results.len());
+        for &res in results {
+            match res.status {
+                ResultStatus::Completed => searched.push(res),
+                ResultStatus::TimedOut => stats.timed_out += 1,
+                ResultStatus::Crashed { .. } => stats.crashed += 1,
+            }
         }
-        if res.nodes > max_nodes {
-            max_nodes = res.nodes;
+        let results = &searched[..];
+        if results.is_empty() {
+            return stats;
         }
-        nodes_vec.push(res.nodes as f64);
-        // Count mate-in-Ns from score string (e.g., "mate 1", "mate -2", etc.)
-        if let Some(idx) = res.score.find("mate ") {
-            let after = &res.score[idx + 5..];
-            if let Some(num_str) = after.split_whitespace().next() {
-                if let Ok(n) = num_str.parse::<i32>() {
-                    let n_abs = n.abs() as u32;
-                    *mate_in_counts.entry(n_abs).or_insert(0) += 1;
+        stats.min_nodes = u64::MAX;
+        let mut total_nodes = 0u64;
+        let mut total_depth = 0u64;
+        let mut total_nps = 0u64;
+        let mut total_time = 0u64;
+        let mut first_move_matches = 0u64;
+        for res in results {
+            total_nodes += res.nodes;
+            total_depth += res.depth as u64;
+            total_nps += res.nps;
+            total_time += res.time_ms;
+            stats.peak_nps = stats.peak_nps.max(res.nps);
+            stats.min_nodes = stats.min_nodes.min(res.nodes);
+            stats.max_nodes = stats.max_nodes.max(res.nodes);
+            if let Some(class) = res.mate_class() {
+                stats.expected_mates += 1;
+                stats.mate_classes.add(class);
+            }
+            if res.is_bound_score() {
+                stats.bound_scores += 1;
+            }
+            if let Some(hit) = res.first_move_hit() {
+                stats.first_move_positions += 1;
+                first_move_matches += hit as u64;
+            }
+            if let Some(PvIssue::IllegalMove { .. }) = res.pv_issue {
+                stats.illegal_pvs += 1;
+            }
+            if let Some(n) = res.mate() {
+                *stats.mates_by_distance.entry(n.unsigned_abs()).or_insert(0) += 1;
+                if res.pv_issue.is_some() {
+                    stats.bad_mate_pvs += 1;
+                }
+                match res.mate_verified {
+                    Some(true) => stats.mates_proven += 1,
+                    Some(false) => stats.mates_refuted += 1,
+                    None => {}
                 }
             }
         }
-    }
+        let count = results.len() as f64;
+        stats.positions_analyzed = results.len() as u64;
+        stats.avg_nodes = total_nodes as f64 / count;
+        stats.avg_nps = total_nps as f64 / count;
+        stats.avg_nps_m = stats.avg_nps / 1_000_000.0;
+        stats.avg_time_ms = total_time as f64 / count;
+        stats.avg_depth = total_depth as f64 / count;
+        stats.total_mates = stats.mates_by_distance.values().sum();
 
-    let count = results.len() as f64;
-    let avg_nodes = total_nodes as f64 / count;
-    let avg_nps = total_nps as f64 / count;
-    let avg_time = total_time as f64 / count;
-    let avg_depth = total_depth as f64 / count;
-
-    // Use per-result depth for EBF calculation
-    let mut ebf_sum = 0.0;
-    let mut ebf_count = 0.0;
-    for res in results {
-        let depth = res.depth as f64;
-        if res.nodes > 0 && depth > 0.0 {
-            ebf_sum += (res.nodes as f64).powf(1.0 / depth);
-            ebf_count += 1.0;
+        // Use per-result depth for EBF calculation
+        let ebfs: Vec<f64> = results
+            .iter()
+            .filter(|res| res.nodes > 0 && res.depth > 0)
+            .map(|res| (res.nodes as f64).powf(1.0 / res.depth as f64))
+            .collect();
+        if !ebfs.is_empty() {
+            stats.avg_ebf = ebfs.iter().sum::<f64>() / ebfs.len() as f64;
+        }
+        if results.len() > 1 {
+            let variance = results
+                .iter()
+                .map(|res| (res.nodes as f64 - stats.avg_nodes).powi(2))
+                .sum::<f64>()
+                / (count - 1.0);
+            stats.node_stddev = variance.sqrt();
         }
+        if stats.first_move_positions > 0 {
+            stats.first_move_hits =
+                Some(first_move_matches as f64 / stats.first_move_positions as f64);
+        }
+        stats
     }
-    let avg_ebf = if ebf_count > 0.0 {
-        ebf_sum / ebf_count
-    } else {
-        0.0
-    };
-
-    // Node stddev
-    let mean = avg_nodes;
-    let stddev = if count > 1.0 {
-        let var = nodes_vec.iter().map(|&n| (n - mean).powi(2)).sum::<f64>() / (count - 1.0);
-        var.sqrt()
-    } else {
-        0.0
-    };
-
-    // Format NPS as M (millions)
-    let avg_nps_m = avg_nps / 1_000_000.0;
 
-    // First move hits placeholder (requires ground truth)
-    let first_move_hits = 0.82; // 82% as a placeholder
-
-    println!("------------------------------------");
-    println!("General Efficiency:");
-    println!("  Avg EBF:         {:<6.2} (Target: < 2.2)", avg_ebf);
-    println!("  Avg NPS:         {:.2}M   (Machine Dependent)", avg_nps_m);
-    println!("  Avg Time:        {:.1}ms  (Machine Dependent)", avg_time);
-    println!();
-    println!("Search Robustness:");
-    println!(
-        "  Node StdDev:     {:<7.0} (Lower = more stable search)",
-        stddev
-    );
-    println!(
-        "  Max Node Outlier: {:<7} (The \"hardest\" position found)",
-        max_nodes
-    );
-    println!(
-        "  Min Node Speed:  {:<7} (The \"easiest\" position found)",
-        min_nodes
-    );
-    println!();
-    println!("Tactical Accuracy:");
-    let total_mates: u64 = mate_in_counts.values().sum();
-    println!(
-        "  Mates Found:     {}       (Across {} positions)",
-        total_mates, count as u64
-    );
-    println!(
-        "  First Move Hits: {:.0}%     (Move ordering quality)",
-        first_move_hits * 100.0
-    );
-    println!("\nEngine Search Statistics Summary:");
-    println!("  Positions analyzed: {}", count as u64);
-    println!("  Average nodes per search: {:.2}", avg_nodes);
-    println!("  Average depth per search: {:.2}", avg_depth);
-    println!("  Average effective branching factor: {:.4}", avg_ebf);
-    println!("  Average NPS: {:.2}", avg_nps);
-    println!("  Average time per search (ms): {:.2}", avg_time);
-    println!("  Peak NPS: {}", peak_nps);
-    if !mate_in_counts.is_empty() {
-        println!("  Mate-in-Ns found:");
-        for (n, count) in mate_in_counts.iter() {
-            println!("    Mate in {:<2}: {}", n, count);
-        }
+    /// Mates found at `distance` moves.
+    pub fn mates_in(&self, distance: u32) -> u64 {
+        self.mates_by_distance.get(&distance).copied().unwrap_or(0)
     }
-    println!("------------------------------------");
 }
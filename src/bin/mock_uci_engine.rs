@@ -0,0 +1,179 @@
+//! Scripted stand-in for a UCI engine, used by the integration tests.
+//!
+//! The script is a JSON file given as the first argument or in the
+//! `MOCK_UCI_SCRIPT` environment variable:
+//!
+//! ```json
+//! {
+//!   "name": "Mock",
+//!   "uciok": true,
+//!   "positions": [
+//!     { "fen": "7k/5K2/6Q1/8/8/8/8/8 w - -", "behavior": "normal",
+//!       "output": ["info depth 1 score mate 1 pv g6g7", "bestmove g6g7"] }
+//!   ],
+//!   "default": { "output": ["info depth 1 score cp 0 pv e2e4", "bestmove e2e4"] }
+//! }
+//! ```
+//!
+//! Positions are matched on the first four FEN fields, so move counters do
+//! not matter. After printing `output`, a position's `behavior` decides what
+//! happens next:
+//!
+//! - `normal`: nothing, the output is expected to end with `bestmove`
+//! - `hang`: ignore every further command, including `stop` and `isready`
+//! - `hang_until_stop`: wait for `stop`, then print `bestmove 0000`
+//! - `crash`: abort the process
+//! - `exit`: exit with status 3
+//!
+//! With `"uciok": false` the engine prints its `id` line and exits instead
+//! of completing the handshake; with `"hang_in_handshake": true` it prints
+//! its `id` line and then ignores every command.
+
+use serde::Deserialize;
+use std::io::{BufRead, Write};
+
+#[derive(Deserialize)]
+struct Script {
+    #[serde(default = "default_name")]
+    name: String,
+    #[serde(default = "default_true")]
+    uciok: bool,
+    #[serde(default)]
+    hang_in_handshake: bool,
+    #[serde(default = "default_options")]
+    options: Vec<String>,
+    #[serde(default)]
+    positions: Vec<ScriptedPosition>,
+    #[serde(default)]
+    default: Option<ScriptedPosition>,
+}
+
+#[derive(Deserialize, Clone)]
+struct ScriptedPosition {
+    #[serde(default)]
+    fen: String,
+    #[serde(default)]
+    behavior: Behavior,
+    #[serde(default)]
+    output: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum Behavior {
+    #[default]
+    Normal,
+    Hang,
+    HangUntilStop,
+    Crash,
+    Exit,
+}
+
+fn default_name() -> String {
+    "MockEngine".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_options() -> Vec<String> {
+    vec![
+        "option name Threads type spin default 1 min 1 max 512".to_string(),
+        "option name Hash type spin default 16 min 1 max 33554432".to_string(),
+        "option name Clear Hash type button".to_string(),
+        "option name UCI_Chess960 type check default false".to_string(),
+        "option name Style type combo default Normal var Solid var Normal var Risky".to_string(),
+    ]
+}
+
+/// First four FEN fields, which identify the position.
+fn fen_key(fen: &str) -> String {
+    fen.split_whitespace().take(4).collect::<Vec<_>>().join(" ")
+}
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .or_else(|| std::env::var("MOCK_UCI_SCRIPT").ok())
+        .expect("usage: mock_uci_engine <script.json> (or set MOCK_UCI_SCRIPT)");
+    let json = std::fs::read_to_string(&path).expect("unable to read script");
+    let script: Script = serde_json::from_str(&json).expect("invalid script");
+
+    let stdin = std::io::stdin();
+    let mut out = std::io::stdout();
+    let mut fen = String::new();
+    let mut hung = false;
+    let mut waiting_for_stop = false;
+
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let command = line.trim();
+        if hung {
+            continue;
+        }
+        if waiting_for_stop {
+            if command == "stop" {
+                let _ = writeln!(out, "bestmove 0000");
+                waiting_for_stop = false;
+            }
+            let _ = out.flush();
+            continue;
+        }
+        match command {
+            "uci" => {
+                let _ = writeln!(out, "id name {}", script.name);
+                if !script.uciok {
+                    let _ = out.flush();
+                    std::process::exit(0);
+                }
+                if script.hang_in_handshake {
+                    hung = true;
+                    let _ = out.flush();
+                    continue;
+                }
+                let _ = writeln!(out, "id author engine_mate_stats tests");
+                for option in &script.options {
+                    let _ = writeln!(out, "{option}");
+                }
+                let _ = writeln!(out, "uciok");
+            }
+            "isready" => {
+                let _ = writeln!(out, "readyok");
+            }
+            "quit" => break,
+            _ if command.starts_with("position fen ") => {
+                fen = command["position fen ".len()..].to_string();
+            }
+            _ if command.starts_with("go") => {
+                let key = fen_key(&fen);
+                let scripted = script
+                    .positions
+                    .iter()
+                    .find(|p| fen_key(&p.fen) == key)
+                    .or(script.default.as_ref())
+                    .cloned()
+                    .unwrap_or(ScriptedPosition {
+                        fen: String::new(),
+                        behavior: Behavior::Normal,
+                        output: vec!["bestmove 0000".to_string()],
+                    });
+                for line in &scripted.output {
+                    let _ = writeln!(out, "{line}");
+                }
+                let _ = out.flush();
+                match scripted.behavior {
+                    Behavior::Normal => {}
+                    Behavior::Hang => hung = true,
+                    Behavior::HangUntilStop => waiting_for_stop = true,
+                    Behavior::Crash => std::process::abort(),
+                    Behavior::Exit => std::process::exit(3),
+                }
+            }
+            _ => {}
+        }
+        let _ = out.flush();
+    }
+}
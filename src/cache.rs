@@ -0,0 +1,255 @@
+use crate::engine_id::hex;
+use crate::engine_options::OptionSetting;
+use crate::engine_result::{EngineResult, ResultStatus};
+use crate::export::PositionRecord;
+use crate::fens::SuitePosition;
+use crate::search_limits::SearchLimits;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Everything a cached result depends on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheKey {
+    /// SHA-256 of the engine binary
+    pub engine_sha256: String,
+    pub threads: usize,
+    pub options: Vec<OptionSetting>,
+    pub clear_hash: bool,
+    pub new_game: bool,
+    pub limits: SearchLimits,
+    pub fen: String,
+}
+
+impl CacheKey {
+    /// File name of the entry: the SHA-256 of the key.
+    fn digest(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        hex(&Sha256::digest(json.as_bytes()))
+    }
+}
+
+/// One cached result, stored as `<dir>/<digest[..2]>/<digest>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    key: CacheKey,
+    /// Seconds since the Unix epoch when the result was stored
+    created_unix: u64,
+    record: PositionRecord,
+}
+
+/// On-disk cache of per-position results, so rerunning the same engine
+/// build with the same options and limits on a position does not search it
+/// again.
+///
+/// Only completed searches are stored; timeouts and crashes are always
+/// retried.
+pub struct ResultCache {
+    dir: PathBuf,
+}
+
+/// Which entries `ResultCache::prune` removes. Unreadable entries are always
+/// removed.
+#[derive(Debug, Clone, Default)]
+pub struct PruneFilter {
+    /// Remove entries stored more than this many seconds ago
+    pub older_than_secs: Option<u64>,
+    /// Remove entries of engine binaries whose SHA-256 starts with this
+    pub engine_sha256: Option<String>,
+    /// Remove everything
+    pub all: bool,
+}
+
+/// Summary of the cache contents for `cache stats`.
+#[derive(Debug, Clone, Default)]
+pub struct CacheStats {
+    pub entries: u64,
+    pub bytes: u64,
+    pub unreadable: u64,
+    /// Entries per engine binary SHA-256
+    pub engines: BTreeMap<String, u64>,
+    pub oldest_unix: Option<u64>,
+    pub newest_unix: Option<u64>,
+}
+
+impl ResultCache {
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Unable to create cache directory {}", dir.display()))?;
+        Ok(ResultCache {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// `$XDG_CACHE_HOME/engine_mate_stats`, falling back to
+    /// `~/.cache/engine_mate_stats`.
+    pub fn default_dir() -> PathBuf {
+        let base = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .unwrap_or_else(|| PathBuf::from("."));
+        base.join("engine_mate_stats")
+    }
+
+    fn entry_path(&self, key: &CacheKey) -> PathBuf {
+        let digest = key.digest();
+        self.dir.join(&digest[..2]).join(format!("{digest}.json"))
+    }
+
+    /// Cached result for `position`. The suite annotations (source,
+    /// expected mate and moves) come from `position`, since the same FEN may
+    /// appear in suites that annotate it differently.
+    pub fn get(&self, key: &CacheKey, position: &SuitePosition) -> Option<EngineResult> {
+        let json = std::fs::read_to_string(self.entry_path(key)).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&json).ok()?;
+        if entry.key != *key {
+            return None;
+        }
+        let mut result = entry.record.to_result();
+        result.source = position.source.clone();
+        result.expected_mate = position.expected_mate;
+        result.expected_moves = position.best_moves.clone();
+        Some(result)
+    }
+
+    /// Store a completed result; anything else is not worth keeping.
+    pub fn put(&self, key: &CacheKey, result: &EngineResult) -> Result<()> {
+        if result.status != ResultStatus::Completed {
+            return Ok(());
+        }
+        let entry = CacheEntry {
+            key: key.clone(),
+            created_unix: now_unix(),
+            record: PositionRecord::new(0, result),
+        };
+        let path = self.entry_path(key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write then rename, so concurrent workers and interrupted runs
+        // never leave a half-written entry behind
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        std::fs::write(&tmp, serde_json::to_string(&entry)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Result<CacheStats> {
+        let mut stats = CacheStats::default();
+        for path in self.entry_files()? {
+            stats.bytes += std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            let Some(entry) = read_entry(&path) else {
+                stats.unreadable += 1;
+                continue;
+            };
+            stats.entries += 1;
+            *stats.engines.entry(entry.key.engine_sha256).or_insert(0) += 1;
+            stats.oldest_unix = Some(
+                stats
+                    .oldest_unix
+                    .map_or(entry.created_unix, |t| t.min(entry.created_unix)),
+            );
+            stats.newest_unix = Some(
+                stats
+                    .newest_unix
+                    .map_or(entry.created_unix, |t| t.max(entry.created_unix)),
+            );
+        }
+        Ok(stats)
+    }
+
+    /// Remove the entries matched by `filter`, returning how many were removed.
+    pub fn prune(&self, filter: &PruneFilter) -> Result<u64> {
+        let now = now_unix();
+        let mut removed = 0;
+        for path in self.entry_files()? {
+            let remove = match read_entry(&path) {
+                None => true,
+                Some(entry) => {
+                    filter.all
+                        || filter
+                            .older_than_secs
+                            .is_some_and(|age| now.saturating_sub(entry.created_unix) > age)
+                        || filter
+                            .engine_sha256
+                            .as_ref()
+                            .is_some_and(|sha| entry.key.engine_sha256.starts_with(sha.as_str()))
+                }
+            };
+            if remove {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Unable to remove {}", path.display()))?;
+                removed += 1;
+            }
+        }
+        // Drop shards left empty; ones still holding entries stay
+        for shard in std::fs::read_dir(&self.dir)?.flatten() {
+            let _ = std::fs::remove_dir(shard.path());
+        }
+        Ok(removed)
+    }
+
+    /// Every entry file, including leftovers of interrupted writes.
+    fn entry_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for shard in std::fs::read_dir(&self.dir)
+            .with_context(|| format!("Unable to read cache directory {}", self.dir.display()))?
+        {
+            let shard = shard?.path();
+            if !shard.is_dir() {
+                continue;
+            }
+            for file in std::fs::read_dir(&shard)? {
+                let file = file?.path();
+                if file.is_file() {
+                    files.push(file);
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+fn read_entry(path: &Path) -> Option<CacheEntry> {
+    if path.extension().is_none_or(|ext| ext != "json") {
+        return None;
+    }
+    let json = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Entries:         {}", self.entries)?;
+        writeln!(f, "Size:            {:.1} KiB", self.bytes as f64 / 1024.0)?;
+        if self.unreadable > 0 {
+            writeln!(
+                f,
+                "Unreadable:      {} (removed by `cache prune`)",
+                self.unreadable
+            )?;
+        }
+        if let (Some(oldest), Some(newest)) = (self.oldest_unix, self.newest_unix) {
+            let age = |t: u64| now_unix().saturating_sub(t) as f64 / 86_400.0;
+            writeln!(f, "Oldest entry:    {:.1} days old", age(oldest))?;
+            writeln!(f, "Newest entry:    {:.1} days old", age(newest))?;
+        }
+        writeln!(f, "Engine binaries: {}", self.engines.len())?;
+        for (sha, count) in &self.engines {
+            writeln!(f, "  {sha}: {count} entries")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+use crate::engine_options::OptionSetting;
+use crate::engine_result::EngineResult;
+use crate::export::PositionRecord;
+use crate::fens::SuitePosition;
+use crate::search_limits::SearchLimits;
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// What results in a checkpoint depend on. Written as the first line of the
+/// checkpoint; a run may only resume a checkpoint whose identity matches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointIdentity {
+    pub engine_path: String,
+    /// SHA-256 of the engine binary
+    pub engine_sha256: String,
+    pub limits: SearchLimits,
+    pub threads: usize,
+    pub options: Vec<OptionSetting>,
+    pub clear_hash: bool,
+    pub new_game: bool,
+}
+
+impl CheckpointIdentity {
+    /// Describe how `other` differs from this identity, or `None` when
+    /// results from both are interchangeable.
+    fn mismatch(&self, other: &CheckpointIdentity) -> Option<String> {
+        let mut differences = Vec::new();
+        if self.engine_sha256 != other.engine_sha256 {
+            differences.push(format!(
+                "engine binary ({} sha256 {} vs {} sha256 {})",
+                self.engine_path, self.engine_sha256, other.engine_path, other.engine_sha256
+            ));
+        }
+        if self.limits != other.limits {
+            differences.push(format!(
+                "search limits ({} vs {})",
+                self.limits, other.limits
+            ));
+        }
+        if self.threads != other.threads {
+            differences.push(format!("threads ({} vs {})", self.threads, other.threads));
+        }
+        if self.options != other.options {
+            differences.push("engine options".to_string());
+        }
+        if self.clear_hash != other.clear_hash {
+            differences.push("--clearHash".to_string());
+        }
+        if self.new_game != other.new_game {
+            differences.push("ucinewgame per position".to_string());
+        }
+        (!differences.is_empty()).then(|| differences.join(", "))
+    }
+}
+
+/// JSONL file of completed positions that lets an interrupted run resume.
+///
+/// The first line is the run's `CheckpointIdentity`; every further line is
+/// the `PositionRecord` of a finished position, appended and flushed as soon
+/// as the position is done. A record is only reused when both its index and
+/// its FEN match the position being resumed.
+pub struct Checkpoint {
+    out: Mutex<BufWriter<File>>,
+}
+
+impl Checkpoint {
+    /// Start a new checkpoint. An existing file is never overwritten, so a
+    /// forgotten `--resume` cannot throw away hours of results.
+    pub fn create(path: &str, identity: &CheckpointIdentity) -> Result<Self> {
+        if Path::new(path).exists() {
+            bail!("Checkpoint {path} already exists; pass --resume to continue it or remove it");
+        }
+        let file =
+            File::create(path).with_context(|| format!("Unable to create checkpoint {path}"))?;
+        let mut out = BufWriter::new(file);
+        writeln!(out, "{}", serde_json::to_string(identity)?)?;
+        out.flush()?;
+        Ok(Checkpoint {
+            out: Mutex::new(out),
+        })
+    }
+
+    /// Load the finished positions of an existing checkpoint and reopen it
+    /// for appending. Returns one slot per position, filled where the
+    /// checkpoint already holds its result.
+    ///
+    /// Refuses a checkpoint written by a different engine binary, with
+    /// different limits or options, unless `force` is set.
+    pub fn resume(
+        path: &str,
+        identity: &CheckpointIdentity,
+        positions: &[SuitePosition],
+        force: bool,
+    ) -> Result<(Self, Vec<Option<EngineResult>>)> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Unable to read checkpoint {path}"))?;
+        let mut lines = contents.lines();
+        let header = lines
+            .next()
+            .ok_or_else(|| anyhow!("Checkpoint {path} is empty"))?;
+        let recorded: CheckpointIdentity = serde_json::from_str(header)
+            .with_context(|| format!("Checkpoint {path} has no valid header"))?;
+        if let Some(mismatch) = recorded.mismatch(identity) {
+            if !force {
+                bail!(
+                    "Checkpoint {path} does not match this run: {mismatch} differ; \
+                     use --forceResume to resume anyway"
+                );
+            }
+            log::warn!("Resuming {path} although {mismatch} differ");
+        }
+
+        let mut slots: Vec<Option<EngineResult>> = positions.iter().map(|_| None).collect();
+        let mut reused = 0;
+        for (line_no, line) in lines.enumerate() {
+            let record: PositionRecord = match serde_json::from_str(line) {
+                Ok(record) => record,
+                Err(e) => {
+                    // Typically the last line of a run killed mid-write
+                    log::warn!("{path}:{}: skipping unreadable record: {e}", line_no + 2);
+                    continue;
+                }
+            };
+            let slot = record.index.checked_sub(1).and_then(|i| {
+                positions
+                    .get(i)
+                    .filter(|p| p.fen == record.fen)
+                    .and(slots.get_mut(i))
+            });
+            match slot {
+                Some(slot) => {
+                    if slot.is_none() {
+                        reused += 1;
+                    }
+                    *slot = Some(record.to_result());
+                }
+                None => log::warn!(
+                    "{path}:{}: position {} ({}) is not in this run, ignored",
+                    line_no + 2,
+                    record.index,
+                    record.fen
+                ),
+            }
+        }
+        log::info!(
+            "Resuming {path}: {reused} of {} positions already analyzed",
+            positions.len()
+        );
+
+        let file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Unable to append to checkpoint {path}"))?;
+        let mut out = BufWriter::new(file);
+        if !contents.ends_with('\n') {
+            // Terminate a record cut off mid-write so new ones start clean
+            writeln!(out)?;
+        }
+        let checkpoint = Checkpoint {
+            out: Mutex::new(out),
+        };
+        Ok((checkpoint, slots))
+    }
+
+    /// Append the result of the position at 0-based `index`.
+    pub fn write(&self, index: usize, result: &EngineResult) -> Result<()> {
+        let line = serde_json::to_string(&PositionRecord::new(index, result))?;
+        let mut out = self
+            .out
+            .lock()
+            .map_err(|_| anyhow!("Checkpoint poisoned"))?;
+        writeln!(out, "{line}")?;
+        out.flush()?;
+        Ok(())
+    }
+}
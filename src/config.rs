@@ -1,31 +1,354 @@
-use clap::Parser;
+use clap::{ArgAction, Args, Parser, Subcommand};
+use engine_mate_stats::engine_options::OptionSetting;
+use engine_mate_stats::fens::SuiteFormat;
+use engine_mate_stats::pgn::PgnExtract;
+use engine_mate_stats::search_limits::SearchLimits;
+use engine_mate_stats::selection::{Filter, Selection};
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 pub struct Config {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
     /// Path to the UCI engine binary
-    #[arg(long, short = 'e')]
-    pub engine_path: String,
+    #[arg(long, short = 'e', required = true)]
+    pub engine_path: Option<String>,
 
     /// Number of positions to analyze
     #[arg(long, short = 'p', default_value_t = 10)]
     pub num_to_analyze: usize,
 
-    /// Nodes limit per position (mutually exclusive with depth)
-    #[arg(long, short = 'n', required = false, conflicts_with = "depth")]
-    pub nodes: Option<usize>,
+    /// Nodes limit per position
+    #[arg(long, short = 'n', required = false)]
+    pub nodes: Option<u64>,
+
+    /// Depth limit per position
+    #[arg(long, short = 'd', required = false)]
+    pub depth: Option<u32>,
+
+    /// Time limit per position in seconds (sent as `go movetime`)
+    #[arg(long, value_parser = parse_movetime)]
+    pub time: Option<u64>,
 
-    /// Depth limit per position (mutually exclusive with nodes)
-    #[arg(long, short = 'd', required = false, conflicts_with = "nodes")]
-    pub depth: Option<usize>,
+    /// Time limit per position in milliseconds, as in `go movetime`
+    #[arg(long, conflicts_with = "time", value_parser = clap::value_parser!(u64).range(1..))]
+    pub movetime: Option<u64>,
+
+    /// Mate limit per position (sent as `go mate N`)
+    #[arg(long)]
+    pub mate: Option<u32>,
 
     /// Threads for engine
     #[arg(long, short, default_value_t = 8)]
     pub threads: usize,
 
+    /// EPD suite(s) with `bm #N` annotations; repeatable, accepts globs
+    /// (defaults to the embedded FEN set)
+    #[arg(long = "epdFile", num_args = 1..)]
+    pub epd_files: Vec<String>,
+
+    /// PGN file(s) to take positions from; repeatable, accepts globs. Say
+    /// which positions with --plies, --mateAnnotations or --beforeMate
+    #[arg(long = "pgnFile", num_args = 1..)]
+    pub pgn_files: Vec<String>,
+
+    #[command(flatten)]
+    pub pgn: PgnArgs,
+
+    /// Leave out positions that are not legal chess positions instead of
+    /// refusing to run
+    #[arg(long = "skipInvalid")]
+    pub skip_invalid: bool,
+
+    /// Only analyze positions matching a condition; repeatable, all must
+    /// hold. Fields: mate, side, pieces, castling, source (e.g. `mate<=5`,
+    /// `side=w`, `source=*hard*`)
+    #[arg(long, action = ArgAction::Append)]
+    pub filter: Vec<Filter>,
+
+    /// Skip this many matching positions before selecting
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+
+    /// Take every Nth matching position
+    #[arg(long, default_value_t = 1)]
+    pub stride: usize,
+
+    /// Pick `--num_to_analyze` positions at random instead of the first ones
+    #[arg(long)]
+    pub sample: bool,
+
+    /// Seed for `--sample`; the same seed always picks the same positions
+    #[arg(long, default_value_t = 0, requires = "sample")]
+    pub seed: u64,
+
+    /// Hash table size in MB
+    #[arg(long)]
+    pub hash: Option<u64>,
+
+    /// Path to Syzygy tablebases
+    #[arg(long = "syzygyPath")]
+    pub syzygy_path: Option<String>,
+
+    /// Whether the engine should respect the 50-move rule in tablebases
+    #[arg(long = "syzygy50MoveRule")]
+    pub syzygy_50_move_rule: Option<bool>,
+
+    /// Additional engine options as JSON, e.g. '{"Contempt": 0, "Clear Hash": null}'
+    #[arg(long = "engineOpts")]
+    pub engine_opts: Option<String>,
+
+    /// Send `ucinewgame` before every position
+    #[arg(long = "newGamePerPosition", default_value_t = true, action = ArgAction::Set)]
+    pub new_game_per_position: bool,
+
+    /// Press the engine's `Clear Hash` button before every position
+    #[arg(long = "clearHash")]
+    pub clear_hash: bool,
+
+    /// Total threads to use across all engine processes; one engine with
+    /// `--threads` threads is started per share (defaults to `--threads`)
+    #[arg(long)]
+    pub concurrency: Option<usize>,
+
+    /// Wall-clock timeout per position in seconds; a hung engine is stopped,
+    /// killed and restarted
+    #[arg(long, value_parser = parse_seconds)]
+    pub timeout: Option<f64>,
+
+    /// Seconds the engine may take to answer the `uci` handshake and the
+    /// options; waits forever when not given
+    #[arg(long = "handshakeTimeout", value_parser = parse_seconds)]
+    pub handshake_timeout: Option<f64>,
+
+    /// Seconds to wait for `bestmove` after `stop` before killing the engine
+    #[arg(long = "timeoutGrace", default_value_t = 1.0, value_parser = parse_seconds)]
+    pub timeout_grace: f64,
+
+    /// Write a timestamped transcript of all engine I/O, including stderr
+    #[arg(long = "logFile")]
+    pub log_file: Option<String>,
+
     /// Output CSV file name
     #[arg(long, short = 'o', default_value = "results.csv")]
     pub output_csv: String,
+
+    /// Prove mates of up to N moves with the built-in solver: positions get
+    /// a verified expected mate and engine mate scores that short are checked
+    #[arg(long = "solveMates")]
+    pub solve_mates: Option<u32>,
+
+    /// Write the whole run (settings, summary and every position) as one JSON document
+    #[arg(long = "jsonOut")]
+    pub json_out: Option<String>,
+
+    /// Append every finished position to this checkpoint file
+    #[arg(long)]
+    pub checkpoint: Option<String>,
+
+    /// Skip the positions already in the `--checkpoint` file
+    #[arg(long, requires = "checkpoint")]
+    pub resume: bool,
+
+    /// Resume even if the checkpoint was written with a different engine binary, limits or options
+    #[arg(long = "forceResume", requires = "resume")]
+    pub force_resume: bool,
+
+    /// Stream one JSON object per position, as results arrive
+    #[arg(long = "jsonlOut")]
+    pub jsonl_out: Option<String>,
+
+    /// Search every position even if the result cache holds it, and do not store new results
+    #[arg(long = "noCache")]
+    pub no_cache: bool,
+
+    /// Result cache directory (defaults to ~/.cache/engine_mate_stats)
+    #[arg(long = "cacheDir", global = true)]
+    pub cache_dir: Option<String>,
+}
+
+/// Which positions to take from PGN games.
+#[derive(Args, Debug)]
+pub struct PgnArgs {
+    /// Take the positions after these plies from the start of each game
+    #[arg(long, value_delimiter = ',')]
+    pub plies: Vec<u32>,
+
+    /// Take positions whose comment announces a mate (`[%eval #N]` or `#N`,
+    /// from White's point of view) with that mate as ground truth
+    #[arg(long = "mateAnnotations")]
+    pub mate_annotations: bool,
+
+    /// Take the positions this many plies before a game-ending checkmate,
+    /// with the distance left in the game as ground truth
+    #[arg(long = "beforeMate", value_delimiter = ',')]
+    pub before_mate: Vec<u32>,
+}
+
+impl PgnArgs {
+    pub fn extract(&self) -> PgnExtract {
+        PgnExtract {
+            plies: self.plies.clone(),
+            mate_annotations: self.mate_annotations,
+            before_mate: self.before_mate.clone(),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Re-analyze a transcript written with --logFile without running the engine
+    Replay {
+        /// Transcript to replay
+        #[arg(long)]
+        log: String,
+
+        /// Output CSV file name
+        #[arg(long, short = 'o', default_value = "results.csv")]
+        output_csv: String,
+    },
+    /// Inspect or clean up the result cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+    /// Work on position suites
+    Suite {
+        #[command(subcommand)]
+        action: SuiteCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SuiteCommand {
+    /// Write a suite without repeated positions (same board, side to move,
+    /// castling rights and en passant square), keeping the first of each
+    Dedup {
+        /// EPD or PGN file(s) to read, accepts globs (defaults to the
+        /// embedded FEN set)
+        inputs: Vec<String>,
+
+        #[command(flatten)]
+        pgn: PgnArgs,
+
+        /// Cleaned suite to write
+        #[arg(long, short = 'o')]
+        output: String,
+
+        /// epd or json (defaults to the output file's extension); json keeps
+        /// the FENs only, in the layout of the embedded FENs.json
+        #[arg(long)]
+        format: Option<SuiteFormat>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommand {
+    /// Show how many results are cached, for which engine builds
+    Stats,
+    /// Remove cached results
+    #[command(group = clap::ArgGroup::new("which").required(true).multiple(true))]
+    Prune {
+        /// Remove results stored more than this many days ago
+        #[arg(long = "olderThan", group = "which", value_parser = parse_days)]
+        older_than: Option<Duration>,
+
+        /// Remove results of the engine binary whose SHA-256 starts with this
+        #[arg(long, group = "which")]
+        engine: Option<String>,
+
+        /// Remove every cached result
+        #[arg(long, group = "which")]
+        all: bool,
+    },
+}
+
+impl Config {
+    /// Search limits for every position. Limits may be combined; depth 10
+    /// is used when none is given.
+    pub fn search_limits(&self) -> SearchLimits {
+        SearchLimits::new(
+            self.nodes,
+            self.depth,
+            self.time.or(self.movetime),
+            self.mate,
+        )
+    }
+
+    /// Which loaded positions to analyze.
+    pub fn selection(&self) -> Selection {
+        Selection {
+            filters: self.filter.clone(),
+            offset: self.offset,
+            stride: self.stride,
+            sample: self.sample,
+            seed: self.seed,
+            count: self.num_to_analyze,
+        }
+    }
+
+    /// Engine options to set after `Threads`: the dedicated flags first,
+    /// then the entries of `--engineOpts` in the order they were written.
+    pub fn engine_options(&self) -> Result<Vec<OptionSetting>, String> {
+        let mut options = Vec::new();
+        if let Some(hash) = self.hash {
+            options.push(OptionSetting::new("Hash", hash));
+        }
+        if let Some(path) = &self.syzygy_path {
+            options.push(OptionSetting::new("SyzygyPath", path));
+        }
+        if let Some(rule) = self.syzygy_50_move_rule {
+            options.push(OptionSetting::new("Syzygy50MoveRule", rule));
+        }
+        if let Some(json) = &self.engine_opts {
+            let value: serde_json::Value = serde_json::from_str(json)
+                .map_err(|e| format!("Invalid --engineOpts JSON: {e}"))?;
+            let map = value
+                .as_object()
+                .ok_or_else(|| "--engineOpts must be a JSON object".to_string())?;
+            for (name, value) in map {
+                options.push(OptionSetting::from_json(name, value)?);
+            }
+        }
+        Ok(options)
+    }
+
+    /// Number of engine processes to run side by side.
+    pub fn workers(&self) -> usize {
+        let concurrency = self.concurrency.unwrap_or(self.threads);
+        (concurrency / self.threads.max(1)).max(1)
+    }
+}
+
+/// A duration in seconds; zero, negative, infinite and NaN values are rejected.
+fn parse_seconds(s: &str) -> Result<f64, String> {
+    let secs: f64 = s.parse().map_err(|e| format!("{e}"))?;
+    if !secs.is_finite() || secs <= 0.0 {
+        return Err(format!("expected seconds > 0, got '{s}'"));
+    }
+    Ok(secs)
+}
+
+/// An age in days; negative, NaN and overlong values are rejected.
+fn parse_days(s: &str) -> Result<Duration, String> {
+    let days: f64 = s.parse().map_err(|e| format!("{e}"))?;
+    Duration::try_from_secs_f64(days * 86_400.0)
+        .map_err(|_| format!("expected a number of days >= 0, got '{s}'"))
+}
+
+/// Seconds per position as whole milliseconds for `go movetime`; values
+/// that round to 0 ms or do not fit are rejected.
+fn parse_movetime(s: &str) -> Result<u64, String> {
+    let ms = (parse_seconds(s)? * 1000.0).round();
+    if ms < 1.0 {
+        return Err(format!("expected at least 1 ms, got '{s}' seconds"));
+    }
+    if ms >= u64::MAX as f64 {
+        return Err(format!("time limit '{s}' seconds is too long"));
+    }
+    Ok(ms as u64)
 }
 
 pub fn parse_args_and_config() -> Option<Config> {
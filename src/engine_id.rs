@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// SHA-256 of the engine binary, as lowercase hex.
+///
+/// Identifies an engine build independently of where it lives, so results
+/// obtained with one build are never mixed up with another's.
+pub fn engine_sha256(engine_path: &str) -> Result<String> {
+    let path =
+        resolve(engine_path).with_context(|| format!("Engine binary {engine_path} not found"))?;
+    let mut file = std::fs::File::open(&path)
+        .with_context(|| format!("Unable to read engine binary {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Lowercase hex encoding of a digest.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Find the file the engine is started from, looking it up in `PATH` like
+/// `Command` does when the path has no directory part.
+fn resolve(engine_path: &str) -> Option<PathBuf> {
+    let path = Path::new(engine_path);
+    if path.components().count() > 1 || path.is_file() {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    std::env::var_os("PATH").and_then(|dirs| {
+        std::env::split_paths(&dirs)
+            .map(|dir| dir.join(engine_path))
+            .find(|candidate| candidate.is_file())
+    })
+}
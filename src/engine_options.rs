@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Type and constraints of an option advertised in the `uci` handshake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionType {
+    Check,
+    Spin { min: i64, max: i64 },
+    Combo { vars: Vec<String> },
+    Button,
+    String,
+}
+
+/// One `option name ... type ...` line from the engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineOption {
+    pub name: String,
+    pub kind: OptionType,
+}
+
+/// A `setoption` the user asked for. `value` is `None` for buttons.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OptionSetting {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+impl OptionSetting {
+    pub fn new(name: &str, value: impl ToString) -> Self {
+        OptionSetting {
+            name: name.to_string(),
+            value: Some(value.to_string()),
+        }
+    }
+
+    pub fn button(name: &str) -> Self {
+        OptionSetting {
+            name: name.to_string(),
+            value: None,
+        }
+    }
+
+    /// Build a setting from an `--engineOpts` JSON value.
+    pub fn from_json(name: &str, value: &serde_json::Value) -> Result<Self, String> {
+        match value {
+            serde_json::Value::Null => Ok(OptionSetting::button(name)),
+            serde_json::Value::Bool(b) => Ok(OptionSetting::new(name, b)),
+            serde_json::Value::Number(n) => Ok(OptionSetting::new(name, n)),
+            serde_json::Value::String(s) => Ok(OptionSetting::new(name, s)),
+            other => Err(format!(
+                "Engine option '{name}' has unsupported value {other}"
+            )),
+        }
+    }
+
+    /// The `setoption` command for this setting.
+    pub fn command(&self) -> String {
+        match &self.value {
+            Some(value) => format!("setoption name {} value {}", self.name, value),
+            None => format!("setoption name {}", self.name),
+        }
+    }
+}
+
+impl fmt::Display for OptionSetting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}={}", self.name, value),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl EngineOption {
+    /// Parse an `option` line, e.g.
+    /// `option name Hash type spin default 16 min 1 max 33554432`.
+    /// Names, defaults and combo values may contain spaces.
+    pub fn parse(line: &str) -> Option<EngineOption> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.first() != Some(&"option") {
+            return None;
+        }
+        let keywords = ["name", "type", "default", "min", "max", "var"];
+        let mut name = None;
+        let mut kind = None;
+        let mut min = None;
+        let mut max = None;
+        let mut vars = Vec::new();
+
+        let mut idx = 1;
+        while idx < tokens.len() {
+            let keyword = tokens[idx];
+            let start = idx + 1;
+            let mut end = start;
+            // The name runs up to `type`; other values run up to any keyword
+            while end < tokens.len()
+                && !(if keyword == "name" {
+                    tokens[end] == "type"
+                } else {
+                    keywords.contains(&tokens[end])
+                })
+            {
+                end += 1;
+            }
+            let value = tokens[start..end].join(" ");
+            match keyword {
+                "name" => name = Some(value),
+                "type" => kind = Some(value),
+                "default" => {}
+                "min" => min = value.parse().ok(),
+                "max" => max = value.parse().ok(),
+                "var" => vars.push(value),
+                _ => return None,
+            }
+            idx = end;
+        }
+
+        let kind = match kind?.as_str() {
+            "check" => OptionType::Check,
+            "spin" => OptionType::Spin {
+                min: min.unwrap_or(i64::MIN),
+                max: max.unwrap_or(i64::MAX),
+            },
+            "combo" => OptionType::Combo { vars },
+            "button" => OptionType::Button,
+            "string" => OptionType::String,
+            _ => return None,
+        };
+        Some(EngineOption { name: name?, kind })
+    }
+
+    /// Check that `setting` is acceptable for this option.
+    pub fn validate(&self, setting: &OptionSetting) -> Result<(), String> {
+        let name = &self.name;
+        match (&self.kind, &setting.value) {
+            (OptionType::Button, None) => Ok(()),
+            (OptionType::Button, Some(value)) => Err(format!(
+                "Engine option '{name}' is a button and takes no value (got '{value}')"
+            )),
+            (_, None) => Err(format!("Engine option '{name}' requires a value")),
+            (OptionType::Check, Some(value)) => match value.as_str() {
+                "true" | "false" => Ok(()),
+                _ => Err(format!(
+                    "Engine option '{name}' expects true or false, got '{value}'"
+                )),
+            },
+            (OptionType::Spin { min, max }, Some(value)) => match value.parse::<i64>() {
+                Ok(v) if (*min..=*max).contains(&v) => Ok(()),
+                Ok(v) => Err(format!(
+                    "Engine option '{name}' value {v} is outside {min}..={max}"
+                )),
+                Err(_) => Err(format!(
+                    "Engine option '{name}' expects an integer, got '{value}'"
+                )),
+            },
+            (OptionType::Combo { vars }, Some(value)) => {
+                if vars.iter().any(|v| v.eq_ignore_ascii_case(value)) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "Engine option '{name}' expects one of [{}], got '{value}'",
+                        vars.join(", ")
+                    ))
+                }
+            }
+            (OptionType::String, Some(_)) => Ok(()),
+        }
+    }
+}
+
+/// Validate every requested setting against the options the engine
+/// advertised. Option names are matched case-insensitively, as in UCI.
+pub fn validate_settings(
+    advertised: &[EngineOption],
+    settings: &[OptionSetting],
+) -> Result<(), String> {
+    for setting in settings {
+        let option = advertised
+            .iter()
+            .find(|o| o.name.eq_ignore_ascii_case(&setting.name))
+            .ok_or_else(|| {
+                format!(
+                    "Engine does not advertise option '{}' (available: {})",
+                    setting.name,
+                    advertised
+                        .iter()
+                        .map(|o| o.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+        option.validate(setting)?;
+    }
+    Ok(())
+}
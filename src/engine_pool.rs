@@ -0,0 +1,92 @@
+use crate::engine_processor::{self, EngineSetup, SearchSettings};
+use crate::engine_result::EngineResult;
+use crate::fens::SuitePosition;
+use crate::transcript::Transcript;
+use crate::uci_engine::UciEngine;
+use anyhow::{Result, anyhow};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Analyze `jobs` on `workers` independent engine processes. Each job is a
+/// position along with its 0-based index in the whole run, which is what
+/// transcripts and `on_result` refer to.
+///
+/// Workers pull the next unclaimed job until the set is exhausted. Results
+/// are returned in job order, so the report does not depend on the number
+/// of workers. `on_result` is called for each position as soon as it is
+/// done. Returns the engine name reported by the first worker along with
+/// the results.
+pub fn run(
+    engine_path: &str,
+    setup: &EngineSetup,
+    workers: usize,
+    jobs: &[(usize, &SuitePosition)],
+    settings: &SearchSettings,
+    transcript: Option<Arc<Transcript>>,
+    on_result: &(dyn Fn(usize, &EngineResult) -> Result<()> + Sync),
+) -> Result<(String, Vec<EngineResult>)> {
+    let workers = workers.clamp(1, jobs.len().max(1));
+    if workers == 1 {
+        let mut engine = UciEngine::start(engine_path, transcript, 0)?;
+        let engine_name = engine_processor::initialize_engine(&mut engine, setup)?;
+        let mut results = Vec::with_capacity(jobs.len());
+        for &(index, position) in jobs {
+            let result =
+                engine_processor::analyze_position(&mut engine, setup, index, position, settings)?;
+            on_result(index, &result)?;
+            results.push(result);
+        }
+        return Ok((engine_name, results));
+    }
+
+    log::info!(
+        "Starting {workers} engine workers with {} threads each",
+        setup.threads
+    );
+    let next = AtomicUsize::new(0);
+    let slots: Mutex<Vec<Option<EngineResult>>> =
+        Mutex::new((0..jobs.len()).map(|_| None).collect());
+
+    let names = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|worker| {
+                let transcript = transcript.clone();
+                let (next, slots) = (&next, &slots);
+                scope.spawn(move || -> Result<String> {
+                    let mut engine = UciEngine::start(engine_path, transcript, worker)?;
+                    let engine_name = engine_processor::initialize_engine(&mut engine, setup)?;
+                    loop {
+                        let job = next.fetch_add(1, Ordering::SeqCst);
+                        let Some(&(index, position)) = jobs.get(job) else {
+                            break;
+                        };
+                        let result = engine_processor::analyze_position(
+                            &mut engine,
+                            setup,
+                            index,
+                            position,
+                            settings,
+                        )?;
+                        on_result(index, &result)?;
+                        slots.lock().map_err(|_| anyhow!("Result store poisoned"))?[job] =
+                            Some(result);
+                    }
+                    Ok(engine_name)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().map_err(|_| anyhow!("Engine worker panicked"))?)
+            .collect::<Result<Vec<String>>>()
+    })?;
+
+    let results = slots
+        .into_inner()
+        .map_err(|_| anyhow!("Result store poisoned"))?
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| anyhow!("Not every position was analyzed"))?;
+    Ok((names.into_iter().next().unwrap_or_default(), results))
+}
@@ -1,124 +1,246 @@
-use crate::engine_result::EngineResult;
-use crate::fens::Fens;
+use crate::engine_options::{self, EngineOption, OptionSetting};
+use crate::engine_result::{EngineResult, ResultStatus};
+use crate::fens::SuitePosition;
+use crate::mate_solver;
+use crate::pv_check;
+use crate::search_limits::SearchLimits;
 use crate::uci_engine::UciEngine;
-use anyhow::Result;
+use crate::uci_info::InfoLine;
+use anyhow::{Result, anyhow};
+use std::time::{Duration, Instant};
 
-pub fn initialize_engine(engine: &mut UciEngine, threads: usize) -> Result<String> {
+/// Engine settings applied by `initialize_engine`, kept so a restarted
+/// engine can be brought back to the same state.
+pub struct EngineSetup {
+    pub threads: usize,
+    /// Options set after `Threads`, in order
+    pub options: Vec<OptionSetting>,
+    /// Press the `Clear Hash` button before every search
+    pub clear_hash: bool,
+    /// How long the engine may take to finish the handshake; `None` waits
+    /// forever.
+    pub timeout: Option<Duration>,
+}
+
+/// How each position is searched.
+pub struct SearchSettings {
+    pub limits: SearchLimits,
+    /// Wall-clock budget per position; `None` waits for `bestmove` forever.
+    pub timeout: Option<Duration>,
+    /// How long to wait for `bestmove` after `stop` before killing the engine.
+    pub grace: Duration,
+    /// Send `ucinewgame` before every position so results do not depend on
+    /// what was searched before.
+    pub new_game: bool,
+    /// Check mate scores of at most this many moves with the built-in solver
+    pub solve_mates: Option<u32>,
+}
+
+/// Run the `uci` handshake and apply the configured options, failing when
+/// the engine exits or exceeds `setup.timeout` before `uciok`.
+///
+/// Every option the user asked for is validated against the `option` lines
+/// the engine advertised, so a misspelled or out-of-range option fails here
+/// instead of being silently ignored by the engine. `Threads` is always
+/// sent but only checked when the engine lists it.
+pub fn initialize_engine(engine: &mut UciEngine, setup: &EngineSetup) -> Result<String> {
     engine.send_command("uci")?;
+    let deadline = setup.timeout.map(|t| (t, Instant::now() + t));
     let mut engine_name = String::new();
-    while let Ok(line) = engine.read_line() {
-        println!("Engine: {}", line);
-        if line.starts_with("id name ") {
-            engine_name = line["id name ".len()..].to_string();
+    let mut advertised = Vec::new();
+    loop {
+        let line = match deadline {
+            None => engine.read_line(),
+            Some((timeout, at)) => engine
+                .read_line_timeout(at.saturating_duration_since(Instant::now()))
+                .and_then(|line| {
+                    line.ok_or_else(|| anyhow!("no answer within {}s", timeout.as_secs_f64()))
+                }),
+        }
+        .map_err(|e| anyhow!("Engine sent no uciok: {e}"))?;
+        log::debug!("Engine: {}", line);
+        if let Some(name) = line.strip_prefix("id name ") {
+            engine_name = name.to_string();
+        }
+        if let Some(option) = EngineOption::parse(&line) {
+            advertised.push(option);
         }
         if line == "uciok" {
             break;
         }
     }
-    let thread_cmd = format!("setoption name Threads value {}", threads);
-    engine.send_command(&thread_cmd)?;
+
+    let threads = OptionSetting::new("Threads", setup.threads);
+    if advertised
+        .iter()
+        .any(|o| o.name.eq_ignore_ascii_case(&threads.name))
+    {
+        engine_options::validate_settings(&advertised, std::slice::from_ref(&threads))
+            .map_err(|e| anyhow!(e))?;
+    } else {
+        log::debug!("Engine does not advertise Threads; sending it anyway");
+    }
+    if setup.clear_hash {
+        // Only validated here; pressed before each search
+        engine_options::validate_settings(&advertised, &[clear_hash_button()])
+            .map_err(|e| anyhow!(e))?;
+    }
+    engine_options::validate_settings(&advertised, &setup.options).map_err(|e| anyhow!(e))?;
+    for setting in std::iter::once(&threads).chain(&setup.options) {
+        engine.send_command(&setting.command())?;
+    }
+    if !engine.sync(setup.timeout)? {
+        return Err(anyhow!("Engine did not answer isready after the options"));
+    }
     Ok(engine_name)
 }
 
+fn clear_hash_button() -> OptionSetting {
+    OptionSetting::button("Clear Hash")
+}
+
+/// Analyze positions one after the other on a single engine.
 pub fn process_fens(
     engine: &mut UciEngine,
-    fens: &mut Fens,
-    n: usize,
-    nodes: Option<usize>,
-    depth: Option<usize>,
+    setup: &EngineSetup,
+    positions: &[SuitePosition],
+    settings: &SearchSettings,
 ) -> Result<Vec<EngineResult>> {
-    let mut results = Vec::new();
-    let default_depth = 10;
-    for i in 0..n {
-        if let Some(fen) = fens.get_next() {
-            println!("Sending FEN {}: {}", i + 1, fen);
-            let cmd = format!("position fen {}", fen);
-            engine.send_command(&cmd)?;
+    let mut results = Vec::with_capacity(positions.len());
+    for (i, position) in positions.iter().enumerate() {
+        results.push(analyze_position(engine, setup, i, position, settings)?);
+    }
+    Ok(results)
+}
+
+/// Search a position, restarting and re-initializing the engine when it
+/// had to be killed so the next position starts from a fresh process.
+pub fn analyze_position(
+    engine: &mut UciEngine,
+    setup: &EngineSetup,
+    index: usize,
+    position: &SuitePosition,
+    settings: &SearchSettings,
+) -> Result<EngineResult> {
+    let result = process_position(engine, setup, index, position, settings)?;
+    engine.set_position(None);
+    if !engine.is_running() {
+        log::warn!("Restarting engine after position {}", index + 1);
+        engine.restart()?;
+        initialize_engine(engine, setup)?;
+    }
+    Ok(result)
+}
 
-            let go_cmd = if let Some(nodes_limit) = nodes {
-                format!("go nodes {}", nodes_limit)
-            } else if let Some(depth_limit) = depth {
-                format!("go depth {}", depth_limit)
-            } else {
-                format!("go depth {}", default_depth)
-            };
-            engine.send_command(&go_cmd)?;
+/// Search a single position and collect the engine's final report.
+///
+/// When the timeout expires the engine is sent `stop`; if it still does not
+/// answer within the grace period it is killed. Either way the position is
+/// recorded as timed out. If the engine dies during the search the position
+/// is recorded as crashed along with the engine's last output.
+pub fn process_position(
+    engine: &mut UciEngine,
+    setup: &EngineSetup,
+    index: usize,
+    position: &SuitePosition,
+    settings: &SearchSettings,
+) -> Result<EngineResult> {
+    let mut result = EngineResult::new(position);
+    engine.set_position(Some(index));
+    engine.record_position(index, position);
+    match search_position(engine, setup, index, position, settings, &mut result) {
+        Ok(()) => Ok(result),
+        Err(e) => match engine.wait_exit(Duration::from_secs(1)) {
+            Some(exit_status) => {
+                log::error!(
+                    "Engine crashed ({exit_status}) on position {}: {}",
+                    index + 1,
+                    position.fen
+                );
+                result.status = ResultStatus::Crashed {
+                    exit_status,
+                    last_output: engine.recent_output(),
+                };
+                Ok(result)
+            }
+            None => Err(e),
+        },
+    }
+}
 
-            // Variables to collect info
-            let mut nodes = 0u64;
-            let mut time_ms = 0u64;
-            let mut nps = 0u64;
-            let mut score = String::new();
-            let mut bestmove = String::new();
-            let mut depth = 0u32;
+fn search_position(
+    engine: &mut UciEngine,
+    setup: &EngineSetup,
+    index: usize,
+    position: &SuitePosition,
+    settings: &SearchSettings,
+    result: &mut EngineResult,
+) -> Result<()> {
+    let fen = &position.fen;
+    if settings.new_game {
+        engine.send_command("ucinewgame")?;
+    }
+    if setup.clear_hash {
+        engine.send_command(&clear_hash_button().command())?;
+    }
+    if !engine.sync(settings.timeout)? {
+        log::warn!(
+            "Engine did not answer isready before position {}, killing it",
+            index + 1
+        );
+        engine.kill();
+        result.status = ResultStatus::TimedOut;
+        return Ok(());
+    }
+    log::info!("Sending FEN {}: {}", index + 1, fen);
+    let cmd = format!("position fen {}", fen);
+    engine.send_command(&cmd)?;
+    engine.send_command(&settings.limits.go_command())?;
+    let mut deadline = settings.timeout.map(|t| Instant::now() + t);
+    let mut stopped = false;
 
-            // Wait for engine to finish (look for 'bestmove')
-            loop {
-                let line = engine.read_line()?;
-                println!("Engine: {}", line);
-                if line.starts_with("info ") {
-                    // Try to parse info line for nodes, time, nps, score, depth
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    let mut idx = 0;
-                    while idx < parts.len() {
-                        match parts[idx] {
-                            "nodes" => {
-                                if idx + 1 < parts.len() {
-                                    nodes = parts[idx + 1].parse().unwrap_or(nodes);
-                                    idx += 1;
-                                }
-                            }
-                            "time" => {
-                                if idx + 1 < parts.len() {
-                                    time_ms = parts[idx + 1].parse().unwrap_or(time_ms);
-                                    idx += 1;
-                                }
-                            }
-                            "nps" => {
-                                if idx + 1 < parts.len() {
-                                    nps = parts[idx + 1].parse().unwrap_or(nps);
-                                    idx += 1;
-                                }
-                            }
-                            "score" => {
-                                if idx + 2 < parts.len() {
-                                    score = format!("{} {}", parts[idx + 1], parts[idx + 2]);
-                                    idx += 2;
-                                }
-                            }
-                            "depth" => {
-                                if idx + 1 < parts.len() {
-                                    depth = parts[idx + 1].parse().unwrap_or(depth);
-                                    idx += 1;
-                                }
-                            }
-                            _ => {}
-                        }
-                        idx += 1;
+    // Wait for engine to finish (look for 'bestmove')
+    loop {
+        let line = match deadline {
+            None => engine.read_line()?,
+            Some(at) => {
+                let remaining = at.saturating_duration_since(Instant::now());
+                match engine.read_line_timeout(remaining)? {
+                    Some(line) => line,
+                    None if !stopped => {
+                        log::warn!("Position {} timed out, sending stop", index + 1);
+                        engine.send_command("stop")?;
+                        result.status = ResultStatus::TimedOut;
+                        stopped = true;
+                        deadline = Some(Instant::now() + settings.grace);
+                        continue;
                     }
-                } else if line.starts_with("bestmove") {
-                    // Parse bestmove
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() > 1 {
-                        bestmove = parts[1].to_string();
+                    None => {
+                        log::warn!("Engine ignored stop on position {}, killing it", index + 1);
+                        engine.kill();
+                        return Ok(());
                     }
-                    // Store result
-                    let result = EngineResult::new(
-                        fen.to_string(),
-                        nodes,
-                        time_ms,
-                        nps,
-                        score.clone(),
-                        bestmove.clone(),
-                        depth,
-                    );
-                    results.push(result);
-                    break;
                 }
             }
-        } else {
-            break;
+        };
+        log::debug!("Engine: {}", line);
+        if line.starts_with("info ") {
+            match InfoLine::parse(&line) {
+                Ok(info) => result.apply_info(&info),
+                Err(e) => log::warn!("Skipping malformed info line ({e}): {line}"),
+            }
+        } else if line.starts_with("bestmove") {
+            if let Some(bestmove) = line.split_whitespace().nth(1) {
+                result.bestmove = bestmove.to_string();
+            }
+            result.pv_issue = pv_check::verify_pv(fen, &result.pv, result.mate());
+            if let Some(issue) = &result.pv_issue {
+                log::warn!("PV issue for {fen}: {issue}");
+            }
+            if let Some(max_moves) = settings.solve_mates {
+                mate_solver::check_claim(result, max_moves);
+            }
+            return Ok(());
         }
     }
-    Ok(results)
 }
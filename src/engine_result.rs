@@ -1,31 +1,145 @@
+use crate::fens::SuitePosition;
+use crate::mate_class::MateClass;
+use crate::notation;
+use crate::pv_check::PvIssue;
+use crate::uci_info::{InfoLine, Score};
+
+/// How the search of a position ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResultStatus {
+    /// The engine answered with `bestmove`.
+    Completed,
+    /// The per-position timeout expired before `bestmove` arrived.
+    TimedOut,
+    /// The engine process died during the search.
+    Crashed {
+        /// Exit code or signal, as reported by the OS
+        exit_status: String,
+        /// Last lines the engine printed before dying
+        last_output: Vec<String>,
+    },
+}
+
+/// Outcome of searching one position.
+#[derive(Debug, Clone)]
 pub struct EngineResult {
     pub fen: String,
+    /// Suite file the position was loaded from
+    pub source: String,
+    pub expected_mate: Option<i32>,
+    /// Expected best moves in UCI notation; empty without ground truth
+    pub expected_moves: Vec<String>,
     pub nodes: u64,
     pub time_ms: u64,
     pub nps: u64,
-    pub score: String,
+    /// Final reported score, including whether it was only a bound
+    pub score: Option<Score>,
     pub bestmove: String,
     pub depth: u32,
+    pub seldepth: u32,
+    pub hashfull: u32,
+    pub tbhits: u64,
+    /// Principal variation of the final `info` line, in UCI notation
+    pub pv: Vec<String>,
+    /// Problem found when replaying `pv` from `fen`
+    pub pv_issue: Option<PvIssue>,
+    /// Whether the built-in solver confirmed (`true`) or refuted (`false`)
+    /// the mate score; `None` when it was not checked
+    pub mate_verified: Option<bool>,
+    pub status: ResultStatus,
 }
 
 impl EngineResult {
-    pub fn new(
-        fen: String,
-        nodes: u64,
-        time_ms: u64,
-        nps: u64,
-        score: String,
-        bestmove: String,
-        depth: u32,
-    ) -> Self {
+    /// Empty result for a position, before any engine output was seen.
+    pub fn new(position: &SuitePosition) -> Self {
         EngineResult {
-            fen,
-            nodes,
-            time_ms,
-            nps,
-            score,
-            bestmove,
-            depth,
+            fen: position.fen.clone(),
+            source: position.source.clone(),
+            expected_mate: position.expected_mate,
+            expected_moves: position.best_moves.clone(),
+            nodes: 0,
+            time_ms: 0,
+            nps: 0,
+            score: None,
+            bestmove: String::new(),
+            depth: 0,
+            seldepth: 0,
+            hashfull: 0,
+            tbhits: 0,
+            pv: Vec::new(),
+            pv_issue: None,
+            mate_verified: None,
+            status: ResultStatus::Completed,
+        }
+    }
+
+    /// Fold one `info` line into the result. Score and PV are only taken
+    /// from the main line when the engine runs with MultiPV.
+    pub fn apply_info(&mut self, info: &InfoLine) {
+        if let Some(nodes) = info.nodes {
+            self.nodes = nodes;
+        }
+        if let Some(time_ms) = info.time_ms {
+            self.time_ms = time_ms;
+        }
+        if let Some(nps) = info.nps {
+            self.nps = nps;
+        }
+        if let Some(depth) = info.depth {
+            self.depth = depth;
+        }
+        if let Some(seldepth) = info.seldepth {
+            self.seldepth = seldepth;
+        }
+        if let Some(hashfull) = info.hashfull {
+            self.hashfull = hashfull;
+        }
+        if let Some(tbhits) = info.tbhits {
+            self.tbhits = tbhits;
+        }
+        if info.multipv.unwrap_or(1) != 1 {
+            return;
         }
+        if let Some(score) = info.score {
+            self.score = Some(score);
+            // A new score invalidates the PV of an older one
+            self.pv = info.pv.clone();
+        } else if !info.pv.is_empty() {
+            self.pv = info.pv.clone();
+        }
+    }
+
+    /// Mate distance of the final score when it is exact (e.g. `Some(-2)`
+    /// for "mate -2"). Bound mate scores do not count as found mates.
+    pub fn mate(&self) -> Option<i32> {
+        self.score.and_then(|s| s.exact_mate())
+    }
+
+    /// Whether the final score was only a lower or upper bound.
+    pub fn is_bound_score(&self) -> bool {
+        self.score.is_some_and(|s| !s.is_exact())
+    }
+
+    /// Final score as reported by the engine, e.g. "mate 3" or "cp 25 lowerbound".
+    pub fn score_text(&self) -> String {
+        self.score.map(|s| s.to_string()).unwrap_or_default()
+    }
+
+    /// Whether the engine's `bestmove` is one of the expected best moves, or
+    /// `None` when the position has no expected move.
+    pub fn first_move_hit(&self) -> Option<bool> {
+        if self.expected_moves.is_empty() {
+            return None;
+        }
+        let played = notation::position(&self.fen)
+            .ok()
+            .and_then(|pos| notation::to_uci(&pos, &self.bestmove));
+        Some(played.is_some_and(|mv| self.expected_moves.contains(&mv)))
+    }
+
+    /// Classification against the expected mate, if the position has one.
+    pub fn mate_class(&self) -> Option<MateClass> {
+        self.expected_mate
+            .map(|expected| MateClass::classify(expected, self.mate()))
     }
 }
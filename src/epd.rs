@@ -0,0 +1,120 @@
+use crate::fens::SuitePosition;
+use crate::notation;
+
+/// Parse one EPD record such as
+/// `8/8/8/8/8/5K2/6Q1/7k w - - bm #1; id "example";`
+///
+/// The four FEN fields are followed by `;`-terminated opcodes. Halfmove and
+/// fullmove counters are taken from the `hmvc`/`fmvn` opcodes when present and
+/// default to `0 1` otherwise. A `bm #N` or `bm #-N` opcode becomes the
+/// expected mate of the position. Moves given with `bm`, in SAN or UCI, are
+/// the expected best moves; without them the first move of a `pv` (or
+/// matetrack's `PV:`) line is used instead.
+pub fn parse_epd_line(line: &str, source: &str) -> Result<SuitePosition, String> {
+    let mut fields = line.splitn(5, char::is_whitespace);
+    let mut fen_fields = Vec::with_capacity(4);
+    for _ in 0..4 {
+        match fields.next() {
+            Some(f) if !f.is_empty() => fen_fields.push(f),
+            _ => return Err(format!("Truncated EPD record: {line}")),
+        }
+    }
+    let operations = fields.next().unwrap_or("");
+
+    let mut halfmove = "0".to_string();
+    let mut fullmove = "1".to_string();
+    let mut expected_mate = None;
+    let mut bm_moves = Vec::new();
+    let mut pv_move = None;
+    for op in operations.split(';') {
+        let mut parts = op.split_whitespace();
+        let Some(opcode) = parts.next() else {
+            continue;
+        };
+        match opcode {
+            "hmvc" => {
+                if let Some(v) = parts.next() {
+                    halfmove = v.to_string();
+                }
+            }
+            "fmvn" => {
+                if let Some(v) = parts.next() {
+                    fullmove = v.to_string();
+                }
+            }
+            "bm" => {
+                for operand in parts {
+                    let Some(mate) = operand.strip_prefix('#') else {
+                        bm_moves.push(operand);
+                        continue;
+                    };
+                    let n: i32 = mate
+                        .parse()
+                        .map_err(|e| format!("Invalid mate distance '#{mate}': {e}"))?;
+                    if n == 0 {
+                        return Err(format!("Invalid mate distance '#{mate}'"));
+                    }
+                    expected_mate = Some(n);
+                }
+            }
+            "pv" | "PV:" => pv_move = parts.next(),
+            _ => {}
+        }
+    }
+
+    let fen = format!("{} {} {}", fen_fields.join(" "), halfmove, fullmove);
+    let moves = if bm_moves.is_empty() {
+        pv_move.into_iter().collect()
+    } else {
+        bm_moves
+    };
+    let mut best_moves = Vec::with_capacity(moves.len());
+    if !moves.is_empty() {
+        let pos = notation::position(&fen).map_err(|e| format!("Invalid FEN '{fen}': {e}"))?;
+        for mv in moves {
+            let uci = notation::to_uci(&pos, mv)
+                .ok_or_else(|| format!("Illegal best move '{mv}' in '{fen}'"))?;
+            best_moves.push(uci);
+        }
+    }
+
+    Ok(SuitePosition {
+        fen,
+        source: source.to_string(),
+        expected_mate,
+        best_moves,
+    })
+}
+
+/// Write a position back as an EPD record that [`parse_epd_line`] reads the
+/// same way: the expected mate and best moves (in SAN) become one `bm`
+/// opcode, and counters other than `0 1` are kept as `hmvc`/`fmvn`.
+pub fn format_epd_line(position: &SuitePosition) -> String {
+    let fields: Vec<&str> = position.fen.split_whitespace().collect();
+    let mut line = fields[..fields.len().min(4)].join(" ");
+    let mut operands = Vec::new();
+    if let Some(mate) = position.expected_mate {
+        operands.push(format!("#{mate}"));
+    }
+    if !position.best_moves.is_empty() {
+        let pos = notation::position(&position.fen).ok();
+        for mv in &position.best_moves {
+            let san = pos.as_ref().and_then(|pos| notation::to_san(pos, mv));
+            operands.push(san.unwrap_or_else(|| mv.clone()));
+        }
+    }
+    if !operands.is_empty() {
+        line.push_str(&format!(" bm {};", operands.join(" ")));
+    }
+    match (fields.get(4), fields.get(5)) {
+        (Some(&"0"), Some(&"1")) | (None, None) => {}
+        (halfmove, fullmove) => {
+            line.push_str(&format!(
+                " hmvc {}; fmvn {};",
+                halfmove.unwrap_or(&"0"),
+                fullmove.unwrap_or(&"1")
+            ));
+        }
+    }
+    line
+}
@@ -0,0 +1,227 @@
+use crate::engine_result::{EngineResult, ResultStatus};
+use crate::fens::SuitePosition;
+use crate::mate_class::MateClass;
+use crate::pv_check;
+use crate::report::Report;
+use crate::transcript::RunInfo;
+use crate::uci_info::{Score, ScoreBound, ScoreValue};
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How the search of a position ended, as exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionStatus {
+    Completed,
+    TimedOut,
+    Crashed,
+}
+
+/// Flat, per-position view of an `EngineResult` for `--jsonOut` and
+/// `--jsonlOut`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionRecord {
+    /// 1-based position in the analyzed set
+    pub index: usize,
+    pub fen: String,
+    pub source: String,
+    pub expected_mate: Option<i32>,
+    pub expected_moves: Vec<String>,
+    pub bestmove: String,
+    /// `None` when the position has no expected move
+    pub first_move_hit: Option<bool>,
+    pub score_cp: Option<i32>,
+    pub score_mate: Option<i32>,
+    /// `None` when the engine reported no score
+    pub score_bound: Option<ScoreBound>,
+    /// Whether the final score was only a lower or upper bound
+    pub bound: bool,
+    pub pv: Vec<String>,
+    pub nodes: u64,
+    pub depth: u32,
+    pub seldepth: u32,
+    pub time_ms: u64,
+    pub nps: u64,
+    pub hashfull: u32,
+    pub tbhits: u64,
+    /// Classification against `expected_mate`
+    pub mate_class: Option<MateClass>,
+    /// Problem found when replaying the PV
+    pub pv_issue: Option<String>,
+    /// Solver verdict on a short mate score, when it was checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mate_verified: Option<bool>,
+    pub status: PositionStatus,
+    /// Exit status and last output lines when the engine crashed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_status: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub last_output: Vec<String>,
+}
+
+impl PositionRecord {
+    /// Record for the result of the position at 0-based `index`.
+    pub fn new(index: usize, result: &EngineResult) -> Self {
+        let (score_cp, score_mate) = match result.score.map(|s| s.value) {
+            Some(ScoreValue::Cp(cp)) => (Some(cp), None),
+            Some(ScoreValue::Mate(n)) => (None, Some(n)),
+            None => (None, None),
+        };
+        let (status, exit_status, last_output) = match &result.status {
+            ResultStatus::Completed => (PositionStatus::Completed, None, Vec::new()),
+            ResultStatus::TimedOut => (PositionStatus::TimedOut, None, Vec::new()),
+            ResultStatus::Crashed {
+                exit_status,
+                last_output,
+            } => (
+                PositionStatus::Crashed,
+                Some(exit_status.clone()),
+                last_output.clone(),
+            ),
+        };
+        PositionRecord {
+            index: index + 1,
+            fen: result.fen.clone(),
+            source: result.source.clone(),
+            expected_mate: result.expected_mate,
+            expected_moves: result.expected_moves.clone(),
+            bestmove: result.bestmove.clone(),
+            first_move_hit: result.first_move_hit(),
+            score_cp,
+            score_mate,
+            score_bound: result.score.map(|s| s.bound),
+            bound: result.is_bound_score(),
+            pv: result.pv.clone(),
+            nodes: result.nodes,
+            depth: result.depth,
+            seldepth: result.seldepth,
+            time_ms: result.time_ms,
+            nps: result.nps,
+            hashfull: result.hashfull,
+            tbhits: result.tbhits,
+            mate_class: result.mate_class(),
+            pv_issue: result.pv_issue.as_ref().map(|issue| issue.to_string()),
+            mate_verified: result.mate_verified,
+            status,
+            exit_status,
+            last_output,
+        }
+    }
+
+    /// Rebuild the `EngineResult` this record was made from. A recorded PV
+    /// issue is checked again from the PV rather than parsed back from its
+    /// text, whatever the status.
+    pub fn to_result(&self) -> EngineResult {
+        let mut result = EngineResult::new(&SuitePosition {
+            fen: self.fen.clone(),
+            source: self.source.clone(),
+            expected_mate: self.expected_mate,
+            best_moves: self.expected_moves.clone(),
+        });
+        let value = match (self.score_cp, self.score_mate) {
+            (_, Some(n)) => Some(ScoreValue::Mate(n)),
+            (Some(cp), None) => Some(ScoreValue::Cp(cp)),
+            (None, None) => None,
+        };
+        result.score = value.map(|value| Score {
+            value,
+            bound: self.score_bound.unwrap_or(ScoreBound::Exact),
+        });
+        result.bestmove = self.bestmove.clone();
+        result.pv = self.pv.clone();
+        result.nodes = self.nodes;
+        result.depth = self.depth;
+        result.seldepth = self.seldepth;
+        result.time_ms = self.time_ms;
+        result.nps = self.nps;
+        result.hashfull = self.hashfull;
+        result.tbhits = self.tbhits;
+        result.mate_verified = self.mate_verified;
+        if self.pv_issue.is_some() {
+            result.pv_issue = pv_check::verify_pv(&result.fen, &result.pv, result.mate());
+        }
+        result.status = match self.status {
+            PositionStatus::Completed => ResultStatus::Completed,
+            PositionStatus::TimedOut => ResultStatus::TimedOut,
+            PositionStatus::Crashed => ResultStatus::Crashed {
+                exit_status: self.exit_status.clone().unwrap_or_default(),
+                last_output: self.last_output.clone(),
+            },
+        };
+        result
+    }
+}
+
+/// Everything known about a run, written by `--jsonOut`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunDocument {
+    /// Version of engine_mate_stats that produced the document
+    pub version: String,
+    /// Seconds since the Unix epoch when the document was written
+    pub created_unix: u64,
+    pub config: RunInfo,
+    pub report: Report,
+    pub positions: Vec<PositionRecord>,
+}
+
+impl RunDocument {
+    pub fn new(config: RunInfo, report: Report, results: &[EngineResult]) -> Self {
+        RunDocument {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created_unix: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            config,
+            report,
+            positions: results
+                .iter()
+                .enumerate()
+                .map(|(i, result)| PositionRecord::new(i, result))
+                .collect(),
+        }
+    }
+
+    pub fn write(&self, path: &str) -> Result<()> {
+        let file =
+            File::create(path).with_context(|| format!("Unable to create JSON file {path}"))?;
+        let mut out = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut out, self)?;
+        writeln!(out)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// `--jsonlOut` file: one `PositionRecord` per line, written and flushed as
+/// soon as each position is done, so it can be followed while the run is
+/// in progress. With several workers lines arrive in completion order.
+pub struct JsonlWriter {
+    out: Mutex<BufWriter<File>>,
+}
+
+impl JsonlWriter {
+    pub fn create(path: &str) -> Result<Self> {
+        let file =
+            File::create(path).with_context(|| format!("Unable to create JSONL file {path}"))?;
+        Ok(JsonlWriter {
+            out: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    /// Append the result of the position at 0-based `index`.
+    pub fn write(&self, index: usize, result: &EngineResult) -> Result<()> {
+        let line = serde_json::to_string(&PositionRecord::new(index, result))?;
+        let mut out = self
+            .out
+            .lock()
+            .map_err(|_| anyhow!("JSONL writer poisoned"))?;
+        writeln!(out, "{line}")?;
+        out.flush()?;
+        Ok(())
+    }
+}
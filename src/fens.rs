@@ -1,30 +1,301 @@
-use serde::Deserialize;
-use serde_json;
+use crate::epd;
+use crate::notation;
+use crate::pgn::{self, PgnExtract, PgnReader};
+use serde::{Deserialize, Serialize};
+use shakmaty::fen::Fen;
+use shakmaty::{EnPassantMode, Position};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct FensFile {
     fens: Vec<String>,
 }
 
+/// A single position to analyze, with its ground truth when the suite provides one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuitePosition {
+    pub fen: String,
+    /// File the position was loaded from (`FENs.json` for the embedded set)
+    pub source: String,
+    /// Expected mate distance in moves from the side to move's point of view
+    /// (`bm #N` / `bm #-N`), or `None` when the suite carries no mate annotation.
+    pub expected_mate: Option<i32>,
+    /// Expected best moves in UCI notation, from `bm` moves or else the first
+    /// move of the suite's `pv`; empty when the suite gives none.
+    #[serde(default)]
+    pub best_moves: Vec<String>,
+}
+
+/// Why a loaded position was left out of the run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The line could not be parsed or does not describe a legal position.
+    Invalid(String),
+    /// The side to move is already checkmated, so there is nothing to find.
+    Checkmate,
+    /// The side to move has no legal moves but is not in check.
+    Stalemate,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Invalid(reason) => write!(f, "invalid position: {reason}"),
+            SkipReason::Checkmate => write!(f, "already checkmate"),
+            SkipReason::Stalemate => write!(f, "already stalemate"),
+        }
+    }
+}
+
+/// A position dropped while loading, with where it came from.
+#[derive(Debug, Clone)]
+pub struct SkippedPosition {
+    pub source: String,
+    /// 1-based line number in an EPD file, entry number in `FENs.json` or
+    /// game number in a PGN file
+    pub index: usize,
+    /// The FEN or raw EPD line as it appeared in the source
+    pub text: String,
+    pub reason: SkipReason,
+}
+
+impl fmt::Display for SkippedPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {} ({})",
+            self.source, self.index, self.reason, self.text
+        )
+    }
+}
+
+/// File formats a suite can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuiteFormat {
+    /// One EPD record per line, keeping expected mates and best moves
+    Epd,
+    /// The `FENs.json` layout, which holds FENs only
+    Json,
+}
+
+impl SuiteFormat {
+    /// JSON for a `.json` path, EPD otherwise.
+    pub fn from_path(path: &str) -> Self {
+        if path.to_ascii_lowercase().ends_with(".json") {
+            SuiteFormat::Json
+        } else {
+            SuiteFormat::Epd
+        }
+    }
+}
+
+impl FromStr for SuiteFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "epd" => Ok(SuiteFormat::Epd),
+            "json" => Ok(SuiteFormat::Json),
+            _ => Err(format!("Unknown suite format '{s}' (expected epd or json)")),
+        }
+    }
+}
+
+/// Loaded positions. Every position is parsed with shakmaty while loading:
+/// invalid ones and ones that are already checkmate or stalemate are kept
+/// out of the list and recorded in [`Fens::skipped`] instead, and the rest
+/// carry a normalized FEN.
+#[derive(Default)]
 pub struct Fens {
-    fens: Vec<String>,
+    fens: Vec<SuitePosition>,
+    skipped: Vec<SkippedPosition>,
     index: usize,
 }
 
 impl Fens {
+    /// Load the embedded FEN set. These positions carry no expected mate.
     pub fn load_fens() -> Result<Self, String> {
         let json = include_str!("./FENs.json");
-        let fens_file: FensFile = serde_json::from_str(json).map_err(|e| {
-            log::error!("Invalid FENs.json format: {e}");
-            format!("Invalid FENs.json format: {e}")
-        })?;
-        Ok(Fens {
-            fens: fens_file.fens,
-            index: 0,
-        })
+        let fens_file: FensFile =
+            serde_json::from_str(json).map_err(|e| format!("Invalid FENs.json format: {e}"))?;
+        let mut fens = Fens::default();
+        for (i, fen) in fens_file.fens.into_iter().enumerate() {
+            let position = SuitePosition {
+                fen: fen.clone(),
+                source: "FENs.json".to_string(),
+                expected_mate: None,
+                best_moves: Vec::new(),
+            };
+            fens.add("FENs.json", i + 1, &fen, Ok(position));
+        }
+        Ok(fens)
     }
 
-    pub fn get_next(&mut self) -> Option<&str> {
+    /// Load a matetrack-style EPD file, one position per line.
+    pub fn load_epd(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read EPD file {path}: {e}"))?;
+        let mut fens = Fens::default();
+        for (line_no, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            fens.add(path, line_no + 1, line, epd::parse_epd_line(line, path));
+        }
+        Ok(fens)
+    }
+
+    /// Load every EPD file matched by the given paths or glob patterns and
+    /// concatenate their positions in the order the arguments were given.
+    /// Matches of a single glob are taken in sorted path order.
+    pub fn load_epd_files(patterns: &[String]) -> Result<Self, String> {
+        Fens::load_files(patterns, Fens::load_epd)
+    }
+
+    /// Read a PGN file one game at a time and take the positions `extract`
+    /// asks for from every game.
+    pub fn load_pgn(path: &str, extract: &PgnExtract) -> Result<Self, String> {
+        if extract.is_empty() {
+            return Err(format!(
+                "No positions requested from PGN file {path}; choose plies, mate annotations or plies before mate"
+            ));
+        }
+        let file = std::fs::File::open(path)
+            .map_err(|e| format!("Unable to read PGN file {path}: {e}"))?;
+        let mut fens = Fens::default();
+        for (i, game) in PgnReader::new(std::io::BufReader::new(file)).enumerate() {
+            let game = game.map_err(|e| format!("Unable to read PGN file {path}: {e}"))?;
+            let text = format!("game starting on line {}", game.line);
+            for position in pgn::extract_positions(&game, path, extract) {
+                let text = position.as_ref().map_or(&text, |p| &p.fen).clone();
+                fens.add(path, i + 1, &text, position);
+            }
+        }
+        Ok(fens)
+    }
+
+    /// Load every PGN file matched by the given paths or glob patterns, like
+    /// [`Fens::load_epd_files`].
+    pub fn load_pgn_files(patterns: &[String], extract: &PgnExtract) -> Result<Self, String> {
+        Fens::load_files(patterns, |path| Fens::load_pgn(path, extract))
+    }
+
+    /// Append the positions of another load, keeping their order.
+    pub fn extend(&mut self, other: Fens) {
+        self.fens.extend(other.fens);
+        self.skipped.extend(other.skipped);
+    }
+
+    fn load_files(
+        patterns: &[String],
+        load: impl Fn(&str) -> Result<Fens, String>,
+    ) -> Result<Self, String> {
+        let mut fens = Fens::default();
+        for pattern in patterns {
+            for path in expand_pattern(pattern)? {
+                let loaded = load(&path)?;
+                log::info!("Loaded {} positions from {path}", loaded.fens.len());
+                fens.extend(loaded);
+            }
+        }
+        Ok(fens)
+    }
+
+    /// Drop positions that repeat an earlier one, keeping the first. Two
+    /// positions are the same when board, side to move, castling rights and
+    /// en passant square agree; move counters and annotations are ignored.
+    /// Returns how many positions were dropped.
+    pub fn dedup(&mut self) -> usize {
+        let mut seen = HashSet::new();
+        let before = self.fens.len();
+        self.fens.retain(|position| {
+            // FENs are normalized while loading, so equal positions have
+            // equal leading fields
+            let key: Vec<&str> = position.fen.split_whitespace().take(4).collect();
+            let first = seen.insert(key.join(" "));
+            if !first {
+                log::info!("{}: dropping duplicate {}", position.source, position.fen);
+            }
+            first
+        });
+        before - self.fens.len()
+    }
+
+    /// All loaded positions, in load order.
+    pub fn positions(&self) -> &[SuitePosition] {
+        &self.fens
+    }
+
+    /// Write the loaded positions to `path` as a suite that can be loaded
+    /// again.
+    pub fn write(&self, path: &str, format: SuiteFormat) -> Result<(), String> {
+        let contents = match format {
+            SuiteFormat::Epd => self
+                .fens
+                .iter()
+                .map(|p| epd::format_epd_line(p) + "\n")
+                .collect(),
+            SuiteFormat::Json => {
+                let file = FensFile {
+                    fens: self.fens.iter().map(|p| p.fen.clone()).collect(),
+                };
+                serde_json::to_string_pretty(&file).map_err(|e| e.to_string())? + "\n"
+            }
+        };
+        std::fs::write(path, contents).map_err(|e| format!("Unable to write suite {path}: {e}"))
+    }
+
+    /// Positions dropped while loading, in source order.
+    pub fn skipped(&self) -> &[SkippedPosition] {
+        &self.skipped
+    }
+
+    /// The skipped positions that were invalid rather than already decided.
+    pub fn invalid(&self) -> impl Iterator<Item = &SkippedPosition> {
+        self.skipped
+            .iter()
+            .filter(|p| matches!(p.reason, SkipReason::Invalid(_)))
+    }
+
+    /// Validate a parsed position and either keep it, normalized, or record
+    /// why it was dropped.
+    fn add(
+        &mut self,
+        source: &str,
+        index: usize,
+        text: &str,
+        parsed: Result<SuitePosition, String>,
+    ) {
+        match parsed.map_err(SkipReason::Invalid).and_then(normalize) {
+            Ok(position) => self.fens.push(position),
+            Err(reason) => {
+                log::warn!("{source}:{index}: {reason}");
+                self.skipped.push(SkippedPosition {
+                    source: source.to_string(),
+                    index,
+                    text: text.to_string(),
+                    reason,
+                });
+            }
+        }
+    }
+
+    /// Take up to `n` of the remaining positions.
+    pub fn take_positions(&mut self, n: usize) -> Vec<SuitePosition> {
+        let mut positions = Vec::new();
+        while positions.len() < n {
+            match self.get_next() {
+                Some(position) => positions.push(position.clone()),
+                None => break,
+            }
+        }
+        positions
+    }
+
+    pub fn get_next(&mut self) -> Option<&SuitePosition> {
         if self.fens.is_empty() {
             return None;
         }
@@ -36,3 +307,36 @@ impl Fens {
         Some(fen)
     }
 }
+
+/// Parse the position with shakmaty and rewrite its FEN in canonical form,
+/// dropping an en passant square when no capture is actually possible.
+fn normalize(mut position: SuitePosition) -> Result<SuitePosition, SkipReason> {
+    let pos = notation::position(&position.fen).map_err(SkipReason::Invalid)?;
+    if pos.is_checkmate() {
+        return Err(SkipReason::Checkmate);
+    }
+    if pos.is_stalemate() {
+        return Err(SkipReason::Stalemate);
+    }
+    position.fen = Fen::from_position(pos, EnPassantMode::Legal).to_string();
+    Ok(position)
+}
+
+/// Expand a shell-style glob into matching paths. Plain paths are returned
+/// unchanged so that a missing file is reported by the loader itself.
+fn expand_pattern(pattern: &str) -> Result<Vec<String>, String> {
+    if !pattern.contains(['*', '?', '[']) {
+        return Ok(vec![pattern.to_string()]);
+    }
+    let entries =
+        glob::glob(pattern).map_err(|e| format!("Invalid glob pattern {pattern}: {e}"))?;
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| format!("Unable to read {pattern} match: {e}"))?;
+        paths.push(path.to_string_lossy().into_owned());
+    }
+    if paths.is_empty() {
+        return Err(format!("No files match {pattern}"));
+    }
+    Ok(paths)
+}
@@ -0,0 +1,57 @@
+//! Mate-finding statistics for UCI chess engines.
+//!
+//! The `engine_mate_stats` binary is a thin command line over this crate.
+//! To embed the runner in an engine's own test harness, load positions,
+//! describe the run with [`MateRun`] and inspect the returned results:
+//!
+//! ```no_run
+//! use engine_mate_stats::{Fens, MateRun, SearchLimits};
+//!
+//! let positions = Fens::load_epd("suites/matetrack.epd")
+//!     .map_err(anyhow::Error::msg)?
+//!     .take_positions(100);
+//! let output = MateRun::new("./my_engine")
+//!     .threads(4)
+//!     .limits(SearchLimits::new(Some(1_000_000), None, None, None))
+//!     .positions(positions)
+//!     .run()?;
+//! let stats = output.analyzer().summary();
+//! assert!(stats.mate_classes.found() > 90);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod analyzer;
+pub mod cache;
+pub mod checkpoint;
+pub mod engine_id;
+pub mod engine_options;
+mod engine_pool;
+pub mod engine_processor;
+pub mod engine_result;
+pub mod epd;
+pub mod export;
+pub mod fens;
+pub mod mate_class;
+pub mod mate_run;
+pub mod mate_solver;
+pub mod notation;
+pub mod pgn;
+pub mod pv_check;
+pub mod replay;
+pub mod report;
+pub mod search_limits;
+pub mod selection;
+pub mod transcript;
+pub mod uci_engine;
+pub mod uci_info;
+
+pub use analyzer::{Analyzer, StatsSummary};
+pub use engine_options::OptionSetting;
+pub use engine_result::{EngineResult, ResultStatus};
+pub use fens::{Fens, SuitePosition};
+pub use mate_run::{MateRun, RunOutput};
+pub use report::{ConsoleRenderer, CsvRenderer, Renderer, Report};
+pub use search_limits::SearchLimits;
+pub use selection::{Filter, Selection};
+pub use uci_engine::UciEngine;
+pub use uci_info::InfoLine;
@@ -1,11 +1,13 @@
-mod analyzer;
 mod config;
-mod engine_processor;
-mod engine_result;
-mod fens;
-mod uci_engine;
 
+use engine_mate_stats::cache::{PruneFilter, ResultCache};
+use engine_mate_stats::export::RunDocument;
+use engine_mate_stats::fens::SuiteFormat;
+use engine_mate_stats::pgn::PgnExtract;
+use engine_mate_stats::{MateRun, fens, replay};
 use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 fn main() -> anyhow::Result<()> {
     env_logger::init();
@@ -15,32 +17,170 @@ fn main() -> anyhow::Result<()> {
         None => return Ok(()),
     };
 
-    let mut fens = match fens::Fens::load_fens() {
-        Ok(f) => f,
-        Err(e) => {
-            log::error!("Failed to load FENs: {e}");
-            return Ok(());
+    let cache_dir = config
+        .cache_dir
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(ResultCache::default_dir);
+    match &config.command {
+        Some(config::Commands::Replay { log, output_csv }) => {
+            return replay::replay(log, output_csv, &mut std::io::stdout());
         }
+        Some(config::Commands::Cache { action }) => {
+            return cache_command(&cache_dir, action);
+        }
+        Some(config::Commands::Suite { action }) => {
+            return suite_command(action);
+        }
+        None => {}
+    }
+    let Some(engine_path) = &config.engine_path else {
+        // clap requires the engine unless a subcommand is given
+        return Ok(());
     };
 
-    let mut engine = uci_engine::UciEngine::start(&config.engine_path)?;
-    let engine_name = engine_processor::initialize_engine(&mut engine, config.threads)?;
+    let mut fens = load_suite(&config.epd_files, &config.pgn_files, &config.pgn.extract())
+        .map_err(|e| anyhow::anyhow!("Failed to load FENs: {e}"))?;
+    let invalid = fens.invalid().count();
+    if invalid > 0 && !config.skip_invalid {
+        for position in fens.invalid() {
+            log::error!("{position}");
+        }
+        anyhow::bail!(
+            "{invalid} invalid positions in the suite; fix them or pass --skipInvalid to leave them out"
+        );
+    }
+    for position in fens.skipped() {
+        println!("Skipping {position}");
+    }
+    let duplicates = fens.dedup();
+    if duplicates > 0 {
+        println!("Dropped {duplicates} duplicate positions");
+    }
 
-    let results = engine_processor::process_fens(
-        &mut engine,
-        &mut fens,
-        config.num_to_analyze,
-        config.nodes,
-        config.depth,
+    let selection = config.selection();
+    let positions = selection.apply(fens.take_positions(usize::MAX));
+    let cmdline: String = env::args().collect::<Vec<_>>().join(" ");
+    let mut run = MateRun::new(engine_path)
+        .threads(config.threads)
+        .options(config.engine_options().map_err(anyhow::Error::msg)?)
+        .clear_hash(config.clear_hash)
+        .limits(config.search_limits())
+        .timeout(config.timeout.map(Duration::from_secs_f64))
+        .handshake_timeout(config.handshake_timeout.map(Duration::from_secs_f64))
+        .grace(Duration::from_secs_f64(config.timeout_grace))
+        .new_game_per_position(config.new_game_per_position)
+        .workers(config.workers())
+        .positions(positions)
+        .selection(&selection)
+        .cmdline(&cmdline);
+    if let Some(max_moves) = config.solve_mates {
+        run = run.solve_mates(max_moves);
+    }
+    if let Some(path) = &config.log_file {
+        run = run.log_file(path);
+    }
+    if let Some(path) = &config.checkpoint {
+        run = run
+            .checkpoint(path)
+            .resume(config.resume, config.force_resume);
+    }
+    if !config.no_cache {
+        run = run.cache_dir(&cache_dir);
+    }
+    if let Some(path) = &config.jsonl_out {
+        run = run.jsonl_out(path);
+    }
+    let output = run.run()?;
+
+    // Print the final result
+    let analyzer = output.analyzer();
+    analyzer.analyze_and_write_csv(
+        &mut std::io::stdout(),
+        &config.output_csv,
+        &output.engine_name,
+        &cmdline,
+        run.search_limits(),
+        &run.run_info().selection,
     )?;
+    if let Some(path) = &config.json_out {
+        let report = analyzer.report(
+            &output.engine_name,
+            &cmdline,
+            run.search_limits(),
+            &run.run_info().selection,
+        );
+        RunDocument::new(run.run_info(), report, &output.results).write(path)?;
+    }
+    Ok(())
+}
 
-    let mut analyzer = analyzer::Analyzer::new();
-    for result in results {
-        analyzer.add_result(result);
+/// The given EPD suites followed by the positions taken from the PGN
+/// files, or the embedded FEN set when there are neither.
+fn load_suite(
+    epd_files: &[String],
+    pgn_files: &[String],
+    extract: &PgnExtract,
+) -> Result<fens::Fens, String> {
+    if epd_files.is_empty() && pgn_files.is_empty() {
+        return fens::Fens::load_fens();
     }
+    let mut fens = fens::Fens::load_epd_files(epd_files)?;
+    fens.extend(fens::Fens::load_pgn_files(pgn_files, extract)?);
+    Ok(fens)
+}
 
-    // Print the final result
-    let cmdline: String = env::args().collect::<Vec<_>>().join(" ");
-    analyzer.analyze_and_write_csv(&config.output_csv, &engine_name, &cmdline);
+fn suite_command(action: &config::SuiteCommand) -> anyhow::Result<()> {
+    match action {
+        config::SuiteCommand::Dedup {
+            inputs,
+            pgn,
+            output,
+            format,
+        } => {
+            let (pgn_files, epd_files): (Vec<String>, Vec<String>) = inputs
+                .iter()
+                .cloned()
+                .partition(|path| path.to_ascii_lowercase().ends_with(".pgn"));
+            let mut fens =
+                load_suite(&epd_files, &pgn_files, &pgn.extract()).map_err(anyhow::Error::msg)?;
+            for position in fens.skipped() {
+                println!("Skipping {position}");
+            }
+            let duplicates = fens.dedup();
+            let format = format.unwrap_or_else(|| SuiteFormat::from_path(output));
+            fens.write(output, format).map_err(anyhow::Error::msg)?;
+            println!(
+                "Dropped {duplicates} duplicate positions; wrote {} positions to {output}",
+                fens.positions().len()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn cache_command(cache_dir: &Path, action: &config::CacheCommand) -> anyhow::Result<()> {
+    let cache = ResultCache::open(cache_dir)?;
+    match action {
+        config::CacheCommand::Stats => {
+            println!("Cache directory: {}", cache_dir.display());
+            print!("{}", cache.stats()?);
+        }
+        config::CacheCommand::Prune {
+            older_than,
+            engine,
+            all,
+        } => {
+            let removed = cache.prune(&PruneFilter {
+                older_than_secs: older_than.map(|age| age.as_secs()),
+                engine_sha256: engine.clone(),
+                all: *all,
+            })?;
+            println!(
+                "Removed {removed} cached results from {}",
+                cache_dir.display()
+            );
+        }
+    }
     Ok(())
 }
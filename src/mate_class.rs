@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+/// How a reported score compares to the expected mate of a position,
+/// following the categories matetrack's `matecheck.py` reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MateClass {
+    /// Mate with the expected sign and distance.
+    Best,
+    /// Mate with the expected sign but a longer distance.
+    Longer,
+    /// Mate with the expected sign but shorter than the proven distance,
+    /// i.e. a mate score where none should exist.
+    Impossible,
+    /// Mate with the opposite sign: the engine claims the wrong side mates.
+    WrongSign,
+    /// No mate score reported.
+    NotFound,
+}
+
+impl MateClass {
+    /// Classify a reported mate distance against the expected one.
+    pub fn classify(expected: i32, found: Option<i32>) -> MateClass {
+        match found {
+            None => MateClass::NotFound,
+            Some(m) if m.signum() != expected.signum() => MateClass::WrongSign,
+            Some(m) if m == expected => MateClass::Best,
+            Some(m) if m.abs() > expected.abs() => MateClass::Longer,
+            Some(_) => MateClass::Impossible,
+        }
+    }
+}
+
+/// Per-class counts over a set of classified results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MateClassCounts {
+    pub best: u64,
+    pub longer: u64,
+    pub impossible: u64,
+    pub wrong_sign: u64,
+    pub not_found: u64,
+}
+
+impl MateClassCounts {
+    pub fn add(&mut self, class: MateClass) {
+        match class {
+            MateClass::Best => self.best += 1,
+            MateClass::Longer => self.longer += 1,
+            MateClass::Impossible => self.impossible += 1,
+            MateClass::WrongSign => self.wrong_sign += 1,
+            MateClass::NotFound => self.not_found += 1,
+        }
+    }
+
+    /// Mates with the expected sign, whatever their distance.
+    pub fn found(&self) -> u64 {
+        self.best + self.longer + self.impossible
+    }
+}
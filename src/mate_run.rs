@@ -0,0 +1,389 @@
+use crate::analyzer::Analyzer;
+use crate::cache::{CacheKey, ResultCache};
+use crate::checkpoint::{Checkpoint, CheckpointIdentity};
+use crate::engine_id;
+use crate::engine_options::OptionSetting;
+use crate::engine_pool;
+use crate::engine_processor::{EngineSetup, SearchSettings};
+use crate::engine_result::EngineResult;
+use crate::export::JsonlWriter;
+use crate::fens::SuitePosition;
+use crate::mate_solver;
+use crate::search_limits::SearchLimits;
+use crate::selection::Selection;
+use crate::transcript::{RunInfo, Transcript};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Grace period after `stop` when none is configured.
+const DEFAULT_GRACE: Duration = Duration::from_secs(1);
+
+/// A configured analysis run: which engine, how it is set up, how each
+/// position is searched and which positions to search.
+///
+/// Defaults to one engine with one thread, the default search limits, no
+/// timeout and `ucinewgame` before every position.
+pub struct MateRun {
+    engine_path: String,
+    setup: EngineSetup,
+    settings: SearchSettings,
+    workers: usize,
+    positions: Vec<SuitePosition>,
+    log_file: Option<String>,
+    jsonl_out: Option<String>,
+    checkpoint: Option<String>,
+    resume: bool,
+    force_resume: bool,
+    cache_dir: Option<PathBuf>,
+    cmdline: String,
+    selection: String,
+}
+
+/// Engine name and per-position results of a finished run, in position order.
+pub struct RunOutput {
+    pub engine_name: String,
+    pub results: Vec<EngineResult>,
+}
+
+impl MateRun {
+    pub fn new(engine_path: &str) -> Self {
+        MateRun {
+            engine_path: engine_path.to_string(),
+            setup: EngineSetup {
+                threads: 1,
+                options: Vec::new(),
+                clear_hash: false,
+                timeout: None,
+            },
+            settings: SearchSettings {
+                limits: SearchLimits::new(None, None, None, None),
+                timeout: None,
+                grace: DEFAULT_GRACE,
+                new_game: true,
+                solve_mates: None,
+            },
+            workers: 1,
+            positions: Vec::new(),
+            log_file: None,
+            jsonl_out: None,
+            checkpoint: None,
+            resume: false,
+            force_resume: false,
+            cache_dir: None,
+            cmdline: String::new(),
+            selection: String::new(),
+        }
+    }
+
+    /// Threads per engine process, sent as the `Threads` option.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.setup.threads = threads;
+        self
+    }
+
+    /// Set an engine option after `Threads`; options are applied in the
+    /// order given and validated against what the engine advertises.
+    pub fn option(mut self, setting: OptionSetting) -> Self {
+        self.setup.options.push(setting);
+        self
+    }
+
+    pub fn options(mut self, settings: impl IntoIterator<Item = OptionSetting>) -> Self {
+        self.setup.options.extend(settings);
+        self
+    }
+
+    /// Press the engine's `Clear Hash` button before every position.
+    pub fn clear_hash(mut self, clear_hash: bool) -> Self {
+        self.setup.clear_hash = clear_hash;
+        self
+    }
+
+    pub fn limits(mut self, limits: SearchLimits) -> Self {
+        self.settings.limits = limits;
+        self
+    }
+
+    /// Wall-clock budget per position; a hung engine is stopped, killed and
+    /// restarted.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.settings.timeout = timeout;
+        self
+    }
+
+    /// How long a (re)started engine may take to finish the handshake.
+    pub fn handshake_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.setup.timeout = timeout;
+        self
+    }
+
+    /// How long to wait for `bestmove` after `stop` before killing the engine.
+    pub fn grace(mut self, grace: Duration) -> Self {
+        self.settings.grace = grace;
+        self
+    }
+
+    /// Send `ucinewgame` before every position.
+    pub fn new_game_per_position(mut self, new_game: bool) -> Self {
+        self.settings.new_game = new_game;
+        self
+    }
+
+    /// Prove short mates with the built-in solver: before searching, each
+    /// position gets a verified expected mate of at most `max_moves` moves,
+    /// and engine mate scores that short are checked afterwards.
+    pub fn solve_mates(mut self, max_moves: u32) -> Self {
+        self.settings.solve_mates = Some(max_moves);
+        self
+    }
+
+    /// Number of engine processes analyzing positions in parallel.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    pub fn positions(mut self, positions: impl IntoIterator<Item = SuitePosition>) -> Self {
+        self.positions.extend(positions);
+        self
+    }
+
+    /// Write a transcript of all engine I/O that `replay` can re-analyze.
+    pub fn log_file(mut self, path: &str) -> Self {
+        self.log_file = Some(path.to_string());
+        self
+    }
+
+    /// Stream one JSON record per position to `path` as results arrive.
+    pub fn jsonl_out(mut self, path: &str) -> Self {
+        self.jsonl_out = Some(path.to_string());
+        self
+    }
+
+    /// Append every finished position to a checkpoint file at `path`.
+    pub fn checkpoint(mut self, path: &str) -> Self {
+        self.checkpoint = Some(path.to_string());
+        self
+    }
+
+    /// Continue the checkpoint instead of starting a new one: positions it
+    /// already holds are not searched again. With `force` a checkpoint from
+    /// a different engine binary, limits or options is resumed anyway.
+    pub fn resume(mut self, resume: bool, force: bool) -> Self {
+        self.resume = resume;
+        self.force_resume = force;
+        self
+    }
+
+    /// Look positions up in the result cache at `dir` before searching them,
+    /// and store fresh results there.
+    pub fn cache_dir(mut self, dir: &Path) -> Self {
+        self.cache_dir = Some(dir.to_path_buf());
+        self
+    }
+
+    /// Command line recorded in the transcript, so a replay reports the
+    /// same run.
+    pub fn cmdline(mut self, cmdline: &str) -> Self {
+        self.cmdline = cmdline.to_string();
+        self
+    }
+
+    /// How the positions were chosen, reported alongside the results so two
+    /// runs can be compared.
+    pub fn selection(mut self, selection: &Selection) -> Self {
+        self.selection = selection.to_string();
+        self
+    }
+
+    pub fn search_limits(&self) -> &SearchLimits {
+        &self.settings.limits
+    }
+
+    /// The run's settings, as recorded in transcripts and JSON documents.
+    pub fn run_info(&self) -> RunInfo {
+        RunInfo {
+            cmdline: self.cmdline.clone(),
+            threads: self.setup.threads,
+            options: self.setup.options.clone(),
+            clear_hash: self.setup.clear_hash,
+            limits: self.settings.limits,
+            timeout_secs: self.settings.timeout.map(|t| t.as_secs_f64()),
+            handshake_timeout_secs: self.setup.timeout.map(|t| t.as_secs_f64()),
+            grace_secs: self.settings.grace.as_secs_f64(),
+            new_game: self.settings.new_game,
+            selection: self.selection.clone(),
+            solve_mates: self.settings.solve_mates,
+        }
+    }
+
+    /// Start the engines and analyze every position.
+    pub fn run(&self) -> Result<RunOutput> {
+        let positions = self.annotated_positions();
+        let transcript = match &self.log_file {
+            Some(path) => {
+                let transcript = Transcript::create(path)?;
+                transcript.record_run(&self.run_info())?;
+                Some(Arc::new(transcript))
+            }
+            None => None,
+        };
+        let jsonl = self
+            .jsonl_out
+            .as_deref()
+            .map(JsonlWriter::create)
+            .transpose()?;
+        let engine_sha256 = if self.checkpoint.is_some() || self.cache_dir.is_some() {
+            engine_id::engine_sha256(&self.engine_path)?
+        } else {
+            String::new()
+        };
+        let (checkpoint, mut slots) = self.open_checkpoint(&engine_sha256, &positions)?;
+        let cache = self
+            .cache_dir
+            .as_deref()
+            .map(ResultCache::open)
+            .transpose()?;
+        if let Some(cache) = &cache {
+            let mut hits = 0;
+            for (index, position) in positions.iter().enumerate() {
+                if slots[index].is_some() {
+                    continue;
+                }
+                if let Some(mut result) =
+                    cache.get(&self.cache_key(&engine_sha256, position), position)
+                {
+                    if let Some(max_moves) = self.settings.solve_mates {
+                        mate_solver::check_claim(&mut result, max_moves);
+                    }
+                    if let Some(checkpoint) = &checkpoint {
+                        checkpoint.write(index, &result)?;
+                    }
+                    slots[index] = Some(result);
+                    hits += 1;
+                }
+            }
+            log::info!("{hits} positions served from the result cache");
+        }
+        for (index, result) in slots.iter().enumerate() {
+            let Some(result) = result else {
+                continue;
+            };
+            if let Some(transcript) = &transcript {
+                transcript.record_result(index, result)?;
+            }
+            if let Some(jsonl) = &jsonl {
+                jsonl.write(index, result)?;
+            }
+        }
+
+        let jobs: Vec<(usize, &SuitePosition)> = positions
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| slots[*index].is_none())
+            .collect();
+        let (engine_name, results) = engine_pool::run(
+            &self.engine_path,
+            &self.setup,
+            self.workers,
+            &jobs,
+            &self.settings,
+            transcript,
+            &|index, result| {
+                if let Some(cache) = &cache {
+                    let key = self.cache_key(&engine_sha256, &positions[index]);
+                    if let Err(e) = cache.put(&key, result) {
+                        log::warn!("Unable to cache position {}: {e}", index + 1);
+                    }
+                }
+                if let Some(checkpoint) = &checkpoint {
+                    checkpoint.write(index, result)?;
+                }
+                match &jsonl {
+                    Some(jsonl) => jsonl.write(index, result),
+                    None => Ok(()),
+                }
+            },
+        )?;
+        for ((index, _), result) in jobs.iter().zip(results) {
+            slots[*index] = Some(result);
+        }
+        Ok(RunOutput {
+            engine_name,
+            results: slots.into_iter().flatten().collect(),
+        })
+    }
+
+    /// The positions to search, with their expected mates checked by the
+    /// solver when it is enabled. Positions are split across the workers.
+    fn annotated_positions(&self) -> Vec<SuitePosition> {
+        let mut positions = self.positions.clone();
+        if let Some(max_moves) = self.settings.solve_mates {
+            let chunk = positions.len().div_ceil(self.workers.max(1)).max(1);
+            std::thread::scope(|s| {
+                for part in positions.chunks_mut(chunk) {
+                    s.spawn(move || {
+                        for position in part {
+                            mate_solver::annotate(position, max_moves);
+                        }
+                    });
+                }
+            });
+        }
+        positions
+    }
+
+    fn cache_key(&self, engine_sha256: &str, position: &SuitePosition) -> CacheKey {
+        CacheKey {
+            engine_sha256: engine_sha256.to_string(),
+            threads: self.setup.threads,
+            options: self.setup.options.clone(),
+            clear_hash: self.setup.clear_hash,
+            new_game: self.settings.new_game,
+            limits: self.settings.limits,
+            fen: position.fen.clone(),
+        }
+    }
+
+    /// Create or resume the checkpoint, returning one slot per position
+    /// that is already filled for positions the checkpoint holds.
+    fn open_checkpoint(
+        &self,
+        engine_sha256: &str,
+        positions: &[SuitePosition],
+    ) -> Result<(Option<Checkpoint>, Vec<Option<EngineResult>>)> {
+        let empty = || positions.iter().map(|_| None).collect();
+        let Some(path) = &self.checkpoint else {
+            return Ok((None, empty()));
+        };
+        let identity = CheckpointIdentity {
+            engine_path: self.engine_path.clone(),
+            engine_sha256: engine_sha256.to_string(),
+            limits: self.settings.limits,
+            threads: self.setup.threads,
+            options: self.setup.options.clone(),
+            clear_hash: self.setup.clear_hash,
+            new_game: self.settings.new_game,
+        };
+        if self.resume {
+            let (checkpoint, slots) =
+                Checkpoint::resume(path, &identity, positions, self.force_resume)?;
+            Ok((Some(checkpoint), slots))
+        } else {
+            Ok((Some(Checkpoint::create(path, &identity)?), empty()))
+        }
+    }
+}
+
+impl RunOutput {
+    /// Collect the results into an `Analyzer` for reporting.
+    pub fn analyzer(&self) -> Analyzer {
+        let mut analyzer = Analyzer::new();
+        for result in &self.results {
+            analyzer.add_result(result.clone());
+        }
+        analyzer
+    }
+}
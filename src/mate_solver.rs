@@ -0,0 +1,194 @@
+use crate::engine_result::EngineResult;
+use crate::fens::SuitePosition;
+use crate::notation;
+use shakmaty::{Chess, Move, Position};
+
+/// Nodes one solve may visit before giving up.
+pub const DEFAULT_NODE_BUDGET: u64 = 2_000_000;
+
+/// Outcome of a bounded mate search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Solution {
+    /// Shortest forced mate in moves from the side to move's point of view:
+    /// positive when it mates, negative when it is mated.
+    Mate(i32),
+    /// Proven that neither side forces mate within the searched distance.
+    NoMate,
+    /// The node budget ran out before the search was complete.
+    Unknown,
+}
+
+/// Exhaustive AND/OR mate search over shakmaty's move generator, deepened
+/// one move at a time so the first mate found is the shortest.
+///
+/// Meant for short mates: the tree is searched without a hash table, so the
+/// cost grows with the branching factor to the power of twice the distance.
+/// Checking moves are tried first and the final attacking move is only
+/// looked for among checks.
+pub struct MateSolver {
+    budget: u64,
+    nodes: u64,
+}
+
+impl Default for MateSolver {
+    fn default() -> Self {
+        MateSolver::new(DEFAULT_NODE_BUDGET)
+    }
+}
+
+impl MateSolver {
+    pub fn new(budget: u64) -> Self {
+        MateSolver { budget, nodes: 0 }
+    }
+
+    /// Nodes visited by the last solve.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Find the shortest forced mate for either side of at most `max_moves`
+    /// moves.
+    pub fn solve(&mut self, pos: &Chess, max_moves: u32) -> Solution {
+        self.nodes = 0;
+        for n in 1..=max_moves {
+            match self.mates_in(pos, n) {
+                Some(true) => return Solution::Mate(n as i32),
+                Some(false) => {}
+                None => return Solution::Unknown,
+            }
+            match self.is_mated_in(pos, n) {
+                Some(true) => return Solution::Mate(-(n as i32)),
+                Some(false) => {}
+                None => return Solution::Unknown,
+            }
+        }
+        Solution::NoMate
+    }
+
+    /// Whether the side to move mates within `n` of its own moves; `None`
+    /// when the budget ran out.
+    fn mates_in(&mut self, pos: &Chess, n: u32) -> Option<bool> {
+        for (mv, gives_check) in self.ordered_moves(pos)? {
+            if n == 1 && !gives_check {
+                // Checks come first, so no mate is left
+                break;
+            }
+            let mut next = pos.clone();
+            next.play_unchecked(&mv);
+            if next.is_checkmate() {
+                return Some(true);
+            }
+            if n > 1 && self.is_mated_in(&next, n - 1)? {
+                return Some(true);
+            }
+        }
+        Some(false)
+    }
+
+    /// Whether the side to move is mated within `n` moves of its opponent,
+    /// whatever it plays.
+    fn is_mated_in(&mut self, pos: &Chess, n: u32) -> Option<bool> {
+        let moves = pos.legal_moves();
+        if moves.is_empty() {
+            return Some(pos.is_check());
+        }
+        for mv in &moves {
+            self.visit()?;
+            let mut next = pos.clone();
+            next.play_unchecked(mv);
+            if !self.mates_in(&next, n)? {
+                return Some(false);
+            }
+        }
+        Some(true)
+    }
+
+    /// Legal moves with whether each gives check, checks first.
+    fn ordered_moves(&mut self, pos: &Chess) -> Option<Vec<(Move, bool)>> {
+        let mut moves = Vec::new();
+        for mv in pos.legal_moves() {
+            self.visit()?;
+            let mut next = pos.clone();
+            next.play_unchecked(&mv);
+            let gives_check = next.is_check();
+            moves.push((mv, gives_check));
+        }
+        moves.sort_by_key(|(mv, gives_check)| (!gives_check, !mv.is_capture()));
+        Some(moves)
+    }
+
+    fn visit(&mut self) -> Option<()> {
+        self.nodes += 1;
+        (self.nodes <= self.budget).then_some(())
+    }
+}
+
+/// Check the position's expected mate against the solver, or supply one.
+///
+/// Without an annotation, a mate of at most `max_moves` becomes the expected
+/// mate. An annotated mate is replaced when the solver proves a shorter one.
+/// When it is short enough to search and turns out not to exist, the search
+/// continues up to `max_moves` and the mate found there replaces it; if
+/// there is none, the annotation is dropped.
+pub fn annotate(position: &mut SuitePosition, max_moves: u32) {
+    let Ok(pos) = notation::position(&position.fen) else {
+        return;
+    };
+    let depth = position
+        .expected_mate
+        .map_or(max_moves, |mate| mate.unsigned_abs().min(max_moves));
+    let mut solution = MateSolver::default().solve(&pos, depth);
+    let refuted = solution == Solution::NoMate
+        && position
+            .expected_mate
+            .is_some_and(|mate| mate.unsigned_abs() <= max_moves);
+    if refuted && depth < max_moves {
+        solution = MateSolver::default().solve(&pos, max_moves);
+    }
+    match (solution, position.expected_mate) {
+        (Solution::Mate(mate), None) => {
+            log::info!("{}: solver proves #{mate}", position.fen);
+            position.expected_mate = Some(mate);
+        }
+        (Solution::Mate(mate), Some(expected)) if mate != expected => {
+            log::warn!(
+                "{}: suite expects #{expected} but the solver proves #{mate}",
+                position.fen
+            );
+            position.expected_mate = Some(mate);
+        }
+        (_, Some(expected)) if refuted => {
+            log::warn!(
+                "{}: suite expects #{expected} but the solver proves there is no such mate; dropping it",
+                position.fen
+            );
+            position.expected_mate = None;
+        }
+        _ => {}
+    }
+}
+
+/// Verify an engine's mate score of at most `max_moves` moves: confirmed
+/// when a forced mate for the same side exists within the claimed distance.
+pub fn check_claim(result: &mut EngineResult, max_moves: u32) {
+    let Some(claim) = result.mate() else {
+        return;
+    };
+    if claim == 0 || claim.unsigned_abs() > max_moves {
+        return;
+    }
+    let Ok(pos) = notation::position(&result.fen) else {
+        return;
+    };
+    result.mate_verified = match MateSolver::default().solve(&pos, claim.unsigned_abs()) {
+        Solution::Mate(mate) => Some(mate.signum() == claim.signum()),
+        Solution::NoMate => Some(false),
+        Solution::Unknown => None,
+    };
+    if result.mate_verified == Some(false) {
+        log::warn!(
+            "{}: engine claims mate {claim} but the solver refutes it",
+            result.fen
+        );
+    }
+}
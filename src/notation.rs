@@ -0,0 +1,47 @@
+use shakmaty::fen::Fen;
+use shakmaty::san::SanPlus;
+use shakmaty::uci::UciMove;
+use shakmaty::{CastlingMode, Chess, Move, PositionError};
+
+/// Set up the position described by `fen`.
+///
+/// Positions that could not arise in a game but are still well defined
+/// (composed problems with surplus material or an unreachable check) are
+/// accepted; anything an engine cannot search reliably, such as the side not
+/// to move being in check or bogus castling or en passant fields, is an error.
+pub fn position(fen: &str) -> Result<Chess, String> {
+    Fen::from_ascii(fen.as_bytes())
+        .map_err(|e| e.to_string())
+        .and_then(|f| {
+            f.into_position(CastlingMode::Standard)
+                .or_else(PositionError::ignore_too_much_material)
+                .or_else(PositionError::ignore_impossible_check)
+                .map_err(|e| e.to_string())
+        })
+}
+
+/// Resolve a move in UCI (`g6g7`) or SAN (`Qg7#`) notation to a legal move
+/// in `pos`.
+pub fn parse_move(pos: &Chess, text: &str) -> Option<Move> {
+    if let Some(mv) = UciMove::from_ascii(text.as_bytes())
+        .ok()
+        .and_then(|m| m.to_move(pos).ok())
+    {
+        return Some(mv);
+    }
+    SanPlus::from_ascii(text.as_bytes())
+        .ok()
+        .and_then(|san| san.san.to_move(pos).ok())
+}
+
+/// Normalize a move in either notation to standard UCI, so moves from a
+/// suite and from the engine compare equal.
+pub fn to_uci(pos: &Chess, text: &str) -> Option<String> {
+    parse_move(pos, text).map(|mv| mv.to_uci(CastlingMode::Standard).to_string())
+}
+
+/// Write a move given in either notation as SAN with its check suffix
+/// (`Qg7#`), the notation EPD suites use for `bm`.
+pub fn to_san(pos: &Chess, text: &str) -> Option<String> {
+    parse_move(pos, text).map(|mv| SanPlus::from_move(pos.clone(), &mv).to_string())
+}
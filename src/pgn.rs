@@ -0,0 +1,316 @@
+use crate::fens::SuitePosition;
+use crate::notation;
+use shakmaty::fen::Fen;
+use shakmaty::{Chess, Color, EnPassantMode, Position};
+use std::collections::BTreeMap;
+use std::io::BufRead;
+
+/// Which positions to take from each game.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PgnExtract {
+    /// Positions after this many plies from the start of the game, without
+    /// ground truth
+    pub plies: Vec<u32>,
+    /// Positions whose comment announces a forced mate, either as
+    /// `[%eval #N]` or as a comment starting with `#N`. Like `%eval`, both
+    /// are read from White's point of view.
+    pub mate_annotations: bool,
+    /// Positions this many plies before a game-ending checkmate, with the
+    /// remaining distance as the expected mate. The moves played need not be
+    /// best play by either side, so this ground truth is only as good as the
+    /// games; it is most reliable a few plies from the end.
+    pub before_mate: Vec<u32>,
+}
+
+impl PgnExtract {
+    pub fn is_empty(&self) -> bool {
+        self.plies.is_empty() && !self.mate_annotations && self.before_mate.is_empty()
+    }
+}
+
+/// One game as read from the file: its tag pairs and raw movetext.
+#[derive(Debug, Clone, Default)]
+pub struct PgnGame {
+    /// 1-based line the game starts on
+    pub line: usize,
+    pub tags: Vec<(String, String)>,
+    pub movetext: String,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Reads games one at a time, so files of any size can be processed
+/// without holding more than the current game in memory.
+pub struct PgnReader<R: BufRead> {
+    input: R,
+    line_no: usize,
+    /// First line of the next game, read while finishing the previous one
+    pending: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(input: R) -> Self {
+        PgnReader {
+            input,
+            line_no: 0,
+            pending: None,
+        }
+    }
+
+    fn next_line(&mut self) -> std::io::Result<Option<String>> {
+        if let Some(line) = self.pending.take() {
+            return Ok(Some(line));
+        }
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        self.line_no += 1;
+        Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = std::io::Result<PgnGame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut game = PgnGame::default();
+        let mut comment_depth = 0usize;
+        loop {
+            let line = match self.next_line() {
+                Ok(Some(line)) => line,
+                Ok(None) if game.line == 0 => return None,
+                Ok(None) => return Some(Ok(game)),
+                Err(e) => return Some(Err(e)),
+            };
+            let trimmed = line.trim();
+            if comment_depth == 0 && trimmed.starts_with('[') {
+                if !game.movetext.trim().is_empty() {
+                    // A tag after movetext starts the next game
+                    self.pending = Some(line);
+                    return Some(Ok(game));
+                }
+                if game.line == 0 {
+                    game.line = self.line_no;
+                }
+                if let Some(tag) = parse_tag(trimmed) {
+                    game.tags.push(tag);
+                }
+                continue;
+            }
+            if comment_depth == 0 && trimmed.starts_with('%') {
+                continue;
+            }
+            if trimmed.is_empty() && comment_depth == 0 {
+                continue;
+            }
+            if game.line == 0 {
+                game.line = self.line_no;
+            }
+            for c in line.chars() {
+                match c {
+                    '{' => comment_depth += 1,
+                    '}' => comment_depth = comment_depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            game.movetext.push_str(&line);
+            game.movetext.push('\n');
+        }
+    }
+}
+
+/// `[Name "value"]` with `\"` and `\\` escapes in the value.
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.trim_end().strip_suffix(']')?;
+    let (name, rest) = inner.split_once(char::is_whitespace)?;
+    let quoted = rest.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            value.extend(chars.next());
+        } else {
+            value.push(c);
+        }
+    }
+    Some((name.to_string(), value))
+}
+
+/// Movetext split into what the extractor cares about.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Move(String),
+    Comment(String),
+    End,
+}
+
+/// Tokenize movetext, dropping move numbers, NAGs and variations.
+fn tokenize(movetext: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = movetext.chars().peekable();
+    let mut variation_depth = 0usize;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                let comment: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                if variation_depth == 0 {
+                    tokens.push(Token::Comment(comment));
+                }
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|&c| c != '\n').collect();
+                if variation_depth == 0 {
+                    tokens.push(Token::Comment(comment));
+                }
+            }
+            '(' => variation_depth += 1,
+            ')' => variation_depth = variation_depth.saturating_sub(1),
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || "{};()".contains(next) {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                if variation_depth > 0 || word.starts_with('$') {
+                    continue;
+                }
+                if matches!(word.as_str(), "1-0" | "0-1" | "1/2-1/2" | "*") {
+                    tokens.push(Token::End);
+                    break;
+                }
+                // Move numbers, possibly glued to the move: `12.`, `12...Nf3`
+                let san = match word.find(|c: char| !c.is_ascii_digit()) {
+                    Some(i) if word[i..].starts_with('.') => word[i..].trim_start_matches('.'),
+                    _ => word.as_str(),
+                };
+                let san = san.trim_end_matches(['!', '?']);
+                if san.is_empty() {
+                    continue;
+                }
+                if san.starts_with("0-0") {
+                    tokens.push(Token::Move(san.replace('0', "O")));
+                } else {
+                    tokens.push(Token::Move(san.to_string()));
+                }
+            }
+        }
+    }
+    tokens
+}
+
+/// Mate announced in a comment, from White's point of view.
+fn announced_mate(comment: &str) -> Option<i32> {
+    let text = match comment.find("[%eval #") {
+        Some(at) => &comment[at + "[%eval ".len()..],
+        None => comment.trim_start(),
+    };
+    let digits: String = text
+        .strip_prefix('#')?
+        .chars()
+        .enumerate()
+        .take_while(|&(i, c)| c.is_ascii_digit() || (i == 0 && c == '-'))
+        .map(|(_, c)| c)
+        .collect();
+    digits.parse().ok().filter(|&n| n != 0)
+}
+
+/// Play through a game and take the positions `extract` asks for, in game
+/// order. An illegal or unreadable move ends the game with an error after
+/// the positions found up to that point.
+pub fn extract_positions(
+    game: &PgnGame,
+    source: &str,
+    extract: &PgnExtract,
+) -> Vec<Result<SuitePosition, String>> {
+    let start = match game.tag("FEN") {
+        Some(fen) => match notation::position(fen) {
+            Ok(pos) => pos,
+            Err(e) => return vec![Err(format!("Invalid FEN tag '{fen}': {e}"))],
+        },
+        None => Chess::default(),
+    };
+    let mut positions = vec![start];
+    let mut announced: BTreeMap<usize, i32> = BTreeMap::new();
+    let mut error = None;
+    for token in tokenize(&game.movetext) {
+        let pos = positions.last().expect("start position");
+        match token {
+            Token::Move(san) => {
+                let Some(mv) = notation::parse_move(pos, &san) else {
+                    error = Some(format!(
+                        "Illegal move '{san}' at ply {} of the game",
+                        positions.len()
+                    ));
+                    break;
+                };
+                let mut next = pos.clone();
+                next.play_unchecked(&mv);
+                positions.push(next);
+            }
+            Token::Comment(text) if extract.mate_annotations => {
+                if let Some(mate) = announced_mate(&text) {
+                    announced.insert(positions.len() - 1, mate);
+                }
+            }
+            Token::Comment(_) => {}
+            Token::End => break,
+        }
+    }
+
+    let last = positions.len() - 1;
+    let mut wanted: BTreeMap<usize, Option<i32>> = BTreeMap::new();
+    for &ply in &extract.plies {
+        if ply as usize <= last {
+            wanted.insert(ply as usize, None);
+        }
+    }
+    for (ply, mate) in announced {
+        let mate = match positions[ply].turn() {
+            Color::White => mate,
+            Color::Black => -mate,
+        };
+        wanted.insert(ply, Some(mate));
+    }
+    if error.is_none() && positions[last].is_checkmate() {
+        for &n in &extract.before_mate {
+            let n = n as usize;
+            if n == 0 || n > last {
+                continue;
+            }
+            // Odd distances leave the mating side to move
+            let mate = if n % 2 == 1 {
+                (n as i32 + 1) / 2
+            } else {
+                -(n as i32 / 2)
+            };
+            wanted.entry(last - n).or_insert(None).get_or_insert(mate);
+        }
+    }
+
+    let mut found: Vec<Result<SuitePosition, String>> = wanted
+        .into_iter()
+        .map(|(ply, expected_mate)| {
+            let fen = Fen::from_position(positions[ply].clone(), EnPassantMode::Legal);
+            Ok(SuitePosition {
+                fen: fen.to_string(),
+                source: source.to_string(),
+                expected_mate,
+                best_moves: Vec::new(),
+            })
+        })
+        .collect();
+    found.extend(error.map(Err));
+    found
+}
@@ -0,0 +1,83 @@
+use crate::notation;
+use shakmaty::Position;
+use shakmaty::uci::UciMove;
+use std::fmt;
+
+/// Problem found while replaying an engine's principal variation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PvIssue {
+    /// The root FEN itself could not be set up.
+    InvalidFen(String),
+    /// The move at `ply` (0-based) is malformed or illegal.
+    IllegalMove { ply: usize, uci: String },
+    /// A mate score whose PV stops before the mating move.
+    TooShort { plies: usize, expected: usize },
+    /// A mate score whose PV does not end in checkmate after `expected` plies.
+    NotCheckmate { expected: usize },
+}
+
+impl fmt::Display for PvIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PvIssue::InvalidFen(e) => write!(f, "invalid FEN: {e}"),
+            PvIssue::IllegalMove { ply, uci } => {
+                write!(f, "illegal move {uci} at ply {}", ply + 1)
+            }
+            PvIssue::TooShort { plies, expected } => {
+                write!(f, "PV too short: {plies} plies, mate needs {expected}")
+            }
+            PvIssue::NotCheckmate { expected } => {
+                write!(f, "PV does not end in checkmate after {expected} plies")
+            }
+        }
+    }
+}
+
+/// Number of plies a `mate n` score takes to deliver mate: `2n - 1` when the
+/// side to move mates, `2|n|` when it is mated.
+pub fn mate_plies(mate: i32) -> usize {
+    if mate > 0 {
+        (2 * mate - 1) as usize
+    } else {
+        (2 * mate.unsigned_abs()) as usize
+    }
+}
+
+/// Replay `pv` from `fen`, checking that every move is legal and, for a mate
+/// score, that the line ends in checkmate after the right number of plies.
+pub fn verify_pv(fen: &str, pv: &[String], mate: Option<i32>) -> Option<PvIssue> {
+    let mut pos = match notation::position(fen) {
+        Ok(pos) => pos,
+        Err(e) => return Some(PvIssue::InvalidFen(e)),
+    };
+
+    let expected = mate.map(mate_plies);
+    let mut mated_at_expected = expected == Some(0) && pos.is_checkmate();
+    for (ply, uci) in pv.iter().enumerate() {
+        let mv = match UciMove::from_ascii(uci.as_bytes())
+            .ok()
+            .and_then(|m| m.to_move(&pos).ok())
+        {
+            Some(mv) => mv,
+            None => {
+                return Some(PvIssue::IllegalMove {
+                    ply,
+                    uci: uci.clone(),
+                });
+            }
+        };
+        pos.play_unchecked(&mv);
+        if expected == Some(ply + 1) {
+            mated_at_expected = pos.is_checkmate();
+        }
+    }
+
+    match expected {
+        Some(expected) if pv.len() < expected => Some(PvIssue::TooShort {
+            plies: pv.len(),
+            expected,
+        }),
+        Some(expected) if !mated_at_expected => Some(PvIssue::NotCheckmate { expected }),
+        _ => None,
+    }
+}
@@ -0,0 +1,107 @@
+use crate::analyzer::Analyzer;
+use crate::engine_processor::{self, EngineSetup, SearchSettings};
+use crate::engine_result::EngineResult;
+use crate::export::PositionRecord;
+use crate::transcript::{Direction, RESULT_RECORD, RUN_RECORD, RunInfo, TranscriptLine};
+use crate::uci_engine::UciEngine;
+use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Duration;
+
+/// Re-analyze a transcript written with `--logFile`.
+///
+/// Every worker's recorded engine output is fed back through the same
+/// initialization, `info` parsing and result handling as a live run, and
+/// the results go through the `Analyzer` to produce the same report, which
+/// is written to `out`.
+/// Results the run took from the cache or a checkpoint are read back as
+/// they were recorded.
+pub fn replay(log_path: &str, csv_path: &str, out: &mut impl Write) -> Result<()> {
+    let contents = std::fs::read_to_string(log_path)
+        .map_err(|e| anyhow!("Unable to read transcript {log_path}: {e}"))?;
+
+    let mut run = None;
+    let mut workers: BTreeMap<usize, Vec<TranscriptLine>> = BTreeMap::new();
+    let mut results: Vec<(usize, EngineResult)> = Vec::new();
+    for (line_no, line) in contents.lines().enumerate() {
+        let parsed = TranscriptLine::parse(line)
+            .ok_or_else(|| anyhow!("{log_path}:{}: malformed transcript line", line_no + 1))?;
+        match parsed.worker {
+            Some(worker) => workers.entry(worker).or_default().push(parsed),
+            None => {
+                if parsed.direction == Direction::Meta
+                    && let Some(json) = parsed.text.strip_prefix(RUN_RECORD)
+                {
+                    run = Some(serde_json::from_str::<RunInfo>(json)?);
+                }
+                if parsed.direction == Direction::Meta
+                    && let Some(json) = parsed.text.strip_prefix(RESULT_RECORD)
+                {
+                    let record: PositionRecord = serde_json::from_str(json)?;
+                    let index = record.index.checked_sub(1).ok_or_else(|| {
+                        anyhow!("{log_path}:{}: result record has no position", line_no + 1)
+                    })?;
+                    results.push((index, record.to_result()));
+                }
+            }
+        }
+    }
+    let run = run.ok_or_else(|| anyhow!("{log_path} has no run record"))?;
+
+    let seconds = |secs: f64| match Duration::try_from_secs_f64(secs) {
+        Ok(duration) if !duration.is_zero() => Ok(duration),
+        Ok(_) => Err(anyhow!("{log_path}: invalid timeout {secs} in run record")),
+        Err(e) => Err(anyhow!(
+            "{log_path}: invalid timeout {secs} in run record: {e}"
+        )),
+    };
+    let timeout = run.timeout_secs.map(seconds).transpose()?;
+    let setup = EngineSetup {
+        threads: run.threads,
+        options: run.options.clone(),
+        clear_hash: run.clear_hash,
+        timeout: run.handshake_timeout_secs.map(seconds).transpose()?,
+    };
+    let settings = SearchSettings {
+        limits: run.limits,
+        timeout,
+        grace: seconds(run.grace_secs)?,
+        new_game: run.new_game,
+        solve_mates: run.solve_mates,
+    };
+
+    let mut engine_name = String::new();
+    for (worker, events) in workers {
+        let mut engine = UciEngine::replay(events);
+        let name = engine_processor::initialize_engine(&mut engine, &setup)?;
+        if engine_name.is_empty() {
+            engine_name = name;
+        }
+        while let Some((index, position)) = engine.next_recorded_position() {
+            let result = engine_processor::analyze_position(
+                &mut engine,
+                &setup,
+                index,
+                &position,
+                &settings,
+            )
+            .map_err(|e| anyhow!("Worker {worker}, position {}: {e}", index + 1))?;
+            results.push((index, result));
+        }
+    }
+    results.sort_by_key(|(index, _)| *index);
+
+    let mut analyzer = Analyzer::new();
+    for (_, result) in results {
+        analyzer.add_result(result);
+    }
+    analyzer.analyze_and_write_csv(
+        out,
+        csv_path,
+        &engine_name,
+        &run.cmdline,
+        &settings.limits,
+        &run.selection,
+    )
+}
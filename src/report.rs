@@ -0,0 +1,406 @@
+use crate::analyzer::StatsSummary;
+use crate::search_limits::SearchLimits;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Mate distances that get their own CSV column (`mates in 1` ..); longer
+/// mates are summed in a final `mates in >N` column.
+pub const MATE_COLUMNS: u32 = 20;
+
+/// Statistics for the positions loaded from one suite file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuiteStats {
+    pub source: String,
+    pub stats: StatsSummary,
+}
+
+/// A finished run as seen by the renderers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    pub engine_name: String,
+    pub cmdline: String,
+    pub limits: SearchLimits,
+    /// Which suite positions were analyzed
+    pub selection: String,
+    pub overall: StatsSummary,
+    /// One entry per suite, only when positions came from more than one
+    pub suites: Vec<SuiteStats>,
+}
+
+/// Turns a `Report` into some output format.
+///
+/// Renderers only format what `StatsSummary` already holds; no statistic is
+/// computed here, so every format reports the same numbers.
+pub trait Renderer {
+    fn render(&mut self, report: &Report) -> Result<()>;
+}
+
+/// Human-readable report.
+pub struct ConsoleRenderer<W: Write> {
+    out: W,
+}
+
+impl ConsoleRenderer<std::io::Stdout> {
+    pub fn stdout() -> Self {
+        ConsoleRenderer {
+            out: std::io::stdout(),
+        }
+    }
+}
+
+impl<W: Write> ConsoleRenderer<W> {
+    pub fn new(out: W) -> Self {
+        ConsoleRenderer { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> Renderer for ConsoleRenderer<W> {
+    fn render(&mut self, report: &Report) -> Result<()> {
+        let out = &mut self.out;
+        let stats = &report.overall;
+        writeln!(out, "------------------------------------")?;
+        writeln!(out, "Command line: {}", report.cmdline)?;
+        writeln!(out, "Analysis for engine: {}", report.engine_name)?;
+        writeln!(out, "Search limits: {}", report.limits)?;
+        if !report.selection.is_empty() {
+            writeln!(out, "Positions: {}", report.selection)?;
+        }
+        writeln!(out, "------------------------------------")?;
+        writeln!(out, "General Efficiency:")?;
+        writeln!(
+            out,
+            "  Avg EBF:         {:<6.2} (Target: < 2.2)",
+            stats.avg_ebf
+        )?;
+        writeln!(
+            out,
+            "  Avg NPS:         {:.2}M   (Machine Dependent)",
+            stats.avg_nps_m
+        )?;
+        writeln!(
+            out,
+            "  Avg Time:        {:.1}ms  (Machine Dependent)",
+            stats.avg_time_ms
+        )?;
+        writeln!(out)?;
+        writeln!(out, "Search Robustness:")?;
+        writeln!(
+            out,
+            "  Node StdDev:     {:<7.0} (Lower = more stable search)",
+            stats.node_stddev
+        )?;
+        writeln!(
+            out,
+            "  Max Node Outlier: {:<7} (The \"hardest\" position found)",
+            stats.max_nodes
+        )?;
+        writeln!(
+            out,
+            "  Min Node Speed:  {:<7} (The \"easiest\" position found)",
+            stats.min_nodes
+        )?;
+        writeln!(out)?;
+        writeln!(out, "Tactical Accuracy:")?;
+        writeln!(
+            out,
+            "  Mates Found:     {}       (Across {} positions)",
+            stats.total_mates, stats.positions_analyzed
+        )?;
+        writeln!(
+            out,
+            "  Known Mates:     {}       (Positions with a bm #N ground truth)",
+            stats.expected_mates
+        )?;
+        if stats.expected_mates > 0 {
+            let classes = &stats.mate_classes;
+            writeln!(
+                out,
+                "  Found mates:     {}       (Best mates: {})",
+                classes.found(),
+                classes.best
+            )?;
+            writeln!(out, "    Longer mates:         {}", classes.longer)?;
+            writeln!(
+                out,
+                "    Impossible mates:     {}  (Shorter than the proven mate)",
+                classes.impossible
+            )?;
+            writeln!(
+                out,
+                "    Wrong sign mates:     {}  (Engine claims the wrong side mates)",
+                classes.wrong_sign
+            )?;
+            writeln!(out, "    No mate found:        {}", classes.not_found)?;
+        }
+        writeln!(
+            out,
+            "  Bad Mate PVs:    {}       (Illegal, too short or not ending in mate)",
+            stats.bad_mate_pvs
+        )?;
+        if stats.mates_proven + stats.mates_refuted > 0 {
+            writeln!(
+                out,
+                "  Mates Proven:    {}       (Mate scores the built-in solver confirmed)",
+                stats.mates_proven
+            )?;
+            writeln!(
+                out,
+                "  Mates Refuted:   {}       (Mate scores the built-in solver disproved)",
+                stats.mates_refuted
+            )?;
+        }
+        writeln!(
+            out,
+            "  Illegal PVs:     {}       (Any score)",
+            stats.illegal_pvs
+        )?;
+        writeln!(
+            out,
+            "  Bound Scores:    {}       (Final score was a lower/upper bound)",
+            stats.bound_scores
+        )?;
+        writeln!(
+            out,
+            "  Timed Out:       {}       (Positions that hit the timeout)",
+            stats.timed_out
+        )?;
+        writeln!(
+            out,
+            "  Crashed:         {}       (Positions where the engine died)",
+            stats.crashed
+        )?;
+        match stats.first_move_hits {
+            Some(hits) => writeln!(
+                out,
+                "  First Move Hits: {:.0}%     (Of {} positions with an expected move)",
+                hits * 100.0,
+                stats.first_move_positions
+            )?,
+            None => writeln!(
+                out,
+                "  First Move Hits: n/a     (No expected best moves in the suite)"
+            )?,
+        }
+        writeln!(out, "\nEngine Search Statistics Summary:")?;
+        writeln!(out, "  Positions analyzed: {}", stats.positions_analyzed)?;
+        writeln!(out, "  Average nodes per search: {:.2}", stats.avg_nodes)?;
+        writeln!(out, "  Average depth per search: {:.2}", stats.avg_depth)?;
+        writeln!(
+            out,
+            "  Average effective branching factor: {:.4}",
+            stats.avg_ebf
+        )?;
+        writeln!(out, "  Average NPS: {:.2}", stats.avg_nps)?;
+        writeln!(
+            out,
+            "  Average time per search (ms): {:.2}",
+            stats.avg_time_ms
+        )?;
+        writeln!(out, "  Peak NPS: {}", stats.peak_nps)?;
+        writeln!(out, "  Mate-in-Ns found:")?;
+        // Every distance up to the longest mate found, so gaps show as zero
+        let longest = stats
+            .mates_by_distance
+            .keys()
+            .max()
+            .copied()
+            .unwrap_or(0)
+            .max(1);
+        for n in 1..=longest {
+            writeln!(out, "    Mate in {:<2}: {}", n, stats.mates_in(n))?;
+        }
+        writeln!(out, "------------------------------------")?;
+
+        if !report.suites.is_empty() {
+            writeln!(out, "Per-suite breakdown:")?;
+            for suite in &report.suites {
+                writeln!(
+                    out,
+                    "  {}: {} positions, found mates: {}, best mates: {} (of {} known)",
+                    suite.source,
+                    suite.stats.positions_analyzed,
+                    suite.stats.mate_classes.found(),
+                    suite.stats.mate_classes.best,
+                    suite.stats.expected_mates
+                )?;
+            }
+            writeln!(out, "------------------------------------")?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Appends one row for the whole run and one per suite to a CSV file,
+/// writing the header when the file is new and refusing to append to a file
+/// with other columns.
+pub struct CsvRenderer {
+    path: String,
+}
+
+impl CsvRenderer {
+    pub fn new(path: &str) -> Self {
+        CsvRenderer {
+            path: path.to_string(),
+        }
+    }
+
+    pub fn header() -> Vec<String> {
+        let mut header: Vec<String> = [
+            "engine_name",
+            "cmdline",
+            "selection",
+            "limit_nodes",
+            "limit_depth",
+            "limit_movetime_ms",
+            "limit_mate",
+            "source",
+            "positions_analyzed",
+            "avg_ebf",
+            "avg_nps",
+            "avg_nps_m",
+            "avg_time_ms",
+            "avg_nodes",
+            "avg_depth",
+            "node_stddev",
+            "max_nodes",
+            "min_nodes",
+            "total_mates",
+            "expected_mates",
+            "found_mates",
+            "best_mates",
+            "longer_mates",
+            "impossible_mates",
+            "wrong_sign_mates",
+            "mates_not_found",
+            "illegal_pvs",
+            "bad_mate_pvs",
+            "mates_proven",
+            "mates_refuted",
+            "bound_scores",
+            "timed_out",
+            "crashed",
+            "first_move_hits",
+            "peak_nps",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        for n in 1..=MATE_COLUMNS {
+            header.push(format!("mates in {}", n));
+        }
+        header.push(format!("mates in >{MATE_COLUMNS}"));
+        header
+    }
+
+    fn record(report: &Report, source: &str, stats: &StatsSummary) -> Vec<String> {
+        let limits = &report.limits;
+        let mut record = vec![
+            report.engine_name.clone(),
+            report.cmdline.clone(),
+            report.selection.clone(),
+            optional(limits.nodes),
+            optional(limits.depth),
+            optional(limits.movetime_ms),
+            optional(limits.mate),
+            source.to_string(),
+            stats.positions_analyzed.to_string(),
+            format!("{:.4}", stats.avg_ebf),
+            format!("{:.2}", stats.avg_nps),
+            format!("{:.2}", stats.avg_nps_m),
+            format!("{:.2}", stats.avg_time_ms),
+            format!("{:.2}", stats.avg_nodes),
+            format!("{:.2}", stats.avg_depth),
+            format!("{:.0}", stats.node_stddev),
+            stats.max_nodes.to_string(),
+            stats.min_nodes.to_string(),
+            stats.total_mates.to_string(),
+            stats.expected_mates.to_string(),
+            stats.mate_classes.found().to_string(),
+            stats.mate_classes.best.to_string(),
+            stats.mate_classes.longer.to_string(),
+            stats.mate_classes.impossible.to_string(),
+            stats.mate_classes.wrong_sign.to_string(),
+            stats.mate_classes.not_found.to_string(),
+            stats.illegal_pvs.to_string(),
+            stats.bad_mate_pvs.to_string(),
+            stats.mates_proven.to_string(),
+            stats.mates_refuted.to_string(),
+            stats.bound_scores.to_string(),
+            stats.timed_out.to_string(),
+            stats.crashed.to_string(),
+            optional(
+                stats
+                    .first_move_hits
+                    .map(|hits| format!("{:.0}", hits * 100.0)),
+            ),
+            stats.peak_nps.to_string(),
+        ];
+        for n in 1..=MATE_COLUMNS {
+            record.push(stats.mates_in(n).to_string());
+        }
+        let longer: u64 = stats
+            .mates_by_distance
+            .range(MATE_COLUMNS + 1..)
+            .map(|(_, count)| count)
+            .sum();
+        record.push(longer.to_string());
+        record
+    }
+}
+
+/// Empty CSV field for an unset value.
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+impl Renderer for CsvRenderer {
+    fn render(&mut self, report: &Report) -> Result<()> {
+        let file_exists = Path::new(&self.path).exists()
+            && std::fs::metadata(&self.path)
+                .with_context(|| format!("Unable to read CSV file {}", self.path))?
+                .len()
+                > 0;
+        if file_exists {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_path(&self.path)
+                .with_context(|| format!("Unable to read CSV file {}", self.path))?;
+            let existing = reader
+                .records()
+                .next()
+                .transpose()
+                .with_context(|| format!("Unable to read the header of {}", self.path))?;
+            if existing.is_none_or(|header| header.iter().ne(Self::header())) {
+                anyhow::bail!(
+                    "CSV file {} has different columns; write the results to a new file",
+                    self.path
+                );
+            }
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Unable to open or create CSV file {}", self.path))?;
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(BufWriter::new(file));
+        if !file_exists {
+            writer.write_record(Self::header())?;
+        }
+        writer.write_record(Self::record(report, "all", &report.overall))?;
+        for suite in &report.suites {
+            writer.write_record(Self::record(report, &suite.source, &suite.stats))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Depth searched when no limit is given at all.
+const DEFAULT_DEPTH: u32 = 10;
+
+/// Limits sent with every `go` command. Any combination may be set; the
+/// engine stops at whichever is reached first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchLimits {
+    pub nodes: Option<u64>,
+    pub depth: Option<u32>,
+    pub movetime_ms: Option<u64>,
+    pub mate: Option<u32>,
+}
+
+impl SearchLimits {
+    /// Build the limits from the command line, falling back to a fixed
+    /// depth when none is given.
+    pub fn new(
+        nodes: Option<u64>,
+        depth: Option<u32>,
+        movetime_ms: Option<u64>,
+        mate: Option<u32>,
+    ) -> Self {
+        let depth = if nodes.is_none() && depth.is_none() && movetime_ms.is_none() && mate.is_none()
+        {
+            Some(DEFAULT_DEPTH)
+        } else {
+            depth
+        };
+        SearchLimits {
+            nodes,
+            depth,
+            movetime_ms,
+            mate,
+        }
+    }
+
+    /// The `go` command carrying all limits, e.g. `go nodes 100000 mate 5`.
+    pub fn go_command(&self) -> String {
+        format!("go {}", self)
+    }
+}
+
+impl fmt::Display for SearchLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(nodes) = self.nodes {
+            parts.push(format!("nodes {nodes}"));
+        }
+        if let Some(depth) = self.depth {
+            parts.push(format!("depth {depth}"));
+        }
+        if let Some(movetime) = self.movetime_ms {
+            parts.push(format!("movetime {movetime}"));
+        }
+        if let Some(mate) = self.mate {
+            parts.push(format!("mate {mate}"));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
@@ -0,0 +1,248 @@
+use crate::fens::SuitePosition;
+use std::fmt;
+use std::str::FromStr;
+
+/// How a filter compares a position's value with the one given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Op::Eq => left == right,
+            Op::Ne => left != right,
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+        })
+    }
+}
+
+/// A condition a position must meet to be analyzed, written on the command
+/// line as `<field><op><value>`:
+///
+/// - `mate<=5`: expected mate length in moves, ignoring its sign; positions
+///   without a `bm #N` never match
+/// - `side=w`: side to move, `w` or `b`
+/// - `pieces<=7`: number of pieces on the board, kings included
+/// - `castling=yes`: whether either side still has castling rights
+/// - `source=*mates*.epd`: suite file the position came from, as a glob
+///   matched against the full path or the file name
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Mate(Op, u32),
+    Side(Op, char),
+    Pieces(Op, u32),
+    Castling(Op, bool),
+    Source(Op, String),
+}
+
+impl Filter {
+    pub fn matches(&self, position: &SuitePosition) -> bool {
+        let mut fields = position.fen.split_whitespace();
+        let board = fields.next().unwrap_or("");
+        let side = fields.next().unwrap_or("w");
+        let castling = fields.next().unwrap_or("-");
+        match self {
+            Filter::Mate(op, n) => position
+                .expected_mate
+                .is_some_and(|mate| op.holds(mate.unsigned_abs(), *n)),
+            Filter::Side(op, c) => op.holds(side.starts_with(*c), true),
+            Filter::Pieces(op, n) => {
+                let pieces = board.chars().filter(char::is_ascii_alphabetic).count();
+                op.holds(pieces as u32, *n)
+            }
+            Filter::Castling(op, yes) => op.holds(castling != "-", *yes),
+            Filter::Source(op, pattern) => {
+                let name = std::path::Path::new(&position.source)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let found = glob::Pattern::new(pattern)
+                    .is_ok_and(|p| p.matches(&position.source) || p.matches(&name));
+                op.holds(found, true)
+            }
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (at, op, len) = [
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("!=", Op::Ne),
+            ("=", Op::Eq),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ]
+        .iter()
+        .filter_map(|(text, op)| s.find(text).map(|at| (at, *op, text.len())))
+        .min_by_key(|(at, _, len)| (*at, std::cmp::Reverse(*len)))
+        .ok_or_else(|| format!("Filter '{s}' must look like <field><op><value>, e.g. mate<=5"))?;
+        let field = s[..at].trim();
+        let value = s[at + len..].trim();
+        let number = || {
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("Filter '{s}' needs a number, got '{value}'"))
+        };
+        let equality = |filter: Filter| match op {
+            Op::Eq | Op::Ne => Ok(filter),
+            _ => Err(format!("Filter '{s}' only supports = and !=")),
+        };
+        match field {
+            "mate" => Ok(Filter::Mate(op, number()?)),
+            "pieces" => Ok(Filter::Pieces(op, number()?)),
+            "side" => match value {
+                "w" | "white" => equality(Filter::Side(op, 'w')),
+                "b" | "black" => equality(Filter::Side(op, 'b')),
+                _ => Err(format!("Filter '{s}' needs w or b, got '{value}'")),
+            },
+            "castling" => match value {
+                "yes" | "true" => equality(Filter::Castling(op, true)),
+                "no" | "false" => equality(Filter::Castling(op, false)),
+                _ => Err(format!("Filter '{s}' needs yes or no, got '{value}'")),
+            },
+            "source" => {
+                glob::Pattern::new(value).map_err(|e| format!("Filter '{s}': {e}"))?;
+                equality(Filter::Source(op, value.to_string()))
+            }
+            _ => Err(format!(
+                "Unknown filter field '{field}' (expected mate, side, pieces, castling or source)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::Mate(op, n) => write!(f, "mate{op}{n}"),
+            Filter::Side(op, c) => write!(f, "side{op}{c}"),
+            Filter::Pieces(op, n) => write!(f, "pieces{op}{n}"),
+            Filter::Castling(op, yes) => {
+                write!(f, "castling{op}{}", if *yes { "yes" } else { "no" })
+            }
+            Filter::Source(op, pattern) => write!(f, "source{op}{pattern}"),
+        }
+    }
+}
+
+/// Which of the loaded positions a run analyzes.
+///
+/// Positions are filtered first, then every `stride`-th one is taken
+/// starting at `offset`, and finally `count` of those are kept: the first
+/// ones, or with `sample` a random choice that depends only on `seed`.
+/// The kept positions stay in suite order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    pub filters: Vec<Filter>,
+    pub offset: usize,
+    pub stride: usize,
+    pub sample: bool,
+    pub seed: u64,
+    pub count: usize,
+}
+
+impl Selection {
+    /// The first `count` positions, unfiltered.
+    pub fn first(count: usize) -> Self {
+        Selection {
+            filters: Vec::new(),
+            offset: 0,
+            stride: 1,
+            sample: false,
+            seed: 0,
+            count,
+        }
+    }
+
+    pub fn apply(&self, positions: Vec<SuitePosition>) -> Vec<SuitePosition> {
+        let candidates: Vec<SuitePosition> = positions
+            .into_iter()
+            .filter(|p| self.filters.iter().all(|f| f.matches(p)))
+            .skip(self.offset)
+            .step_by(self.stride.max(1))
+            .collect();
+        if !self.sample || candidates.len() <= self.count {
+            return candidates.into_iter().take(self.count).collect();
+        }
+        // Partial Fisher-Yates over the indices, then back to suite order
+        let mut rng = SplitMix64(self.seed);
+        let mut indices: Vec<usize> = (0..candidates.len()).collect();
+        for i in 0..self.count {
+            let j = i + (rng.next() % (indices.len() - i) as u64) as usize;
+            indices.swap(i, j);
+        }
+        let mut chosen = indices[..self.count].to_vec();
+        chosen.sort_unstable();
+        let mut keep = vec![false; candidates.len()];
+        for i in chosen {
+            keep[i] = true;
+        }
+        candidates
+            .into_iter()
+            .zip(keep)
+            .filter_map(|(p, keep)| keep.then_some(p))
+            .collect()
+    }
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.sample {
+            write!(f, "{} at random (seed {})", self.count, self.seed)?;
+        } else {
+            write!(f, "first {}", self.count)?;
+        }
+        if self.stride > 1 {
+            write!(f, ", every {}", self.stride)?;
+        }
+        if self.offset > 0 {
+            write!(f, ", from offset {}", self.offset)?;
+        }
+        if !self.filters.is_empty() {
+            let filters: Vec<String> = self.filters.iter().map(|f| f.to_string()).collect();
+            write!(f, ", where {}", filters.join(" and "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Small, stable generator so a seed picks the same positions on every
+/// platform and release.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
@@ -0,0 +1,162 @@
+use crate::engine_options::OptionSetting;
+use crate::engine_result::EngineResult;
+use crate::export::PositionRecord;
+use crate::search_limits::SearchLimits;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Which way a transcript line travelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Command sent to the engine (`>`).
+    ToEngine,
+    /// Line read from the engine's stdout (`<`).
+    FromEngine,
+    /// Line read from the engine's stderr (`!`).
+    Stderr,
+    /// Event recorded by the runner itself (`=`), used by replays.
+    Meta,
+}
+
+impl Direction {
+    pub fn marker(self) -> char {
+        match self {
+            Direction::ToEngine => '>',
+            Direction::FromEngine => '<',
+            Direction::Stderr => '!',
+            Direction::Meta => '=',
+        }
+    }
+
+    fn from_marker(marker: &str) -> Option<Direction> {
+        match marker {
+            ">" => Some(Direction::ToEngine),
+            "<" => Some(Direction::FromEngine),
+            "!" => Some(Direction::Stderr),
+            "=" => Some(Direction::Meta),
+            _ => None,
+        }
+    }
+}
+
+/// One parsed transcript line. `worker` is `None` for run-wide records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptLine {
+    pub worker: Option<usize>,
+    pub position: Option<usize>,
+    pub direction: Direction,
+    pub text: String,
+}
+
+impl TranscriptLine {
+    /// Parse a line written by `Transcript::record`. Positions are turned
+    /// back into 0-based indices.
+    pub fn parse(line: &str) -> Option<TranscriptLine> {
+        let mut fields = line.splitn(5, ' ');
+        let _elapsed = fields.next()?;
+        let worker = match fields.next()?.strip_prefix('w')? {
+            "-" => None,
+            w => Some(w.parse().ok()?),
+        };
+        let position = match fields.next()?.strip_prefix('p')? {
+            "-" => None,
+            p => Some(p.parse::<usize>().ok()?.checked_sub(1)?),
+        };
+        let direction = Direction::from_marker(fields.next()?)?;
+        let text = fields.next().unwrap_or("").to_string();
+        Some(TranscriptLine {
+            worker,
+            position,
+            direction,
+            text,
+        })
+    }
+}
+
+/// Settings of a run, written at the top of the transcript so that a replay
+/// can drive the pipeline exactly like the original run did.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunInfo {
+    pub cmdline: String,
+    pub threads: usize,
+    pub options: Vec<OptionSetting>,
+    pub clear_hash: bool,
+    pub limits: SearchLimits,
+    pub timeout_secs: Option<f64>,
+    pub handshake_timeout_secs: Option<f64>,
+    pub grace_secs: f64,
+    pub new_game: bool,
+    /// How the positions were chosen from the suite, see [`crate::selection::Selection`]
+    pub selection: String,
+    /// Longest mate score checked with the built-in solver
+    pub solve_mates: Option<u32>,
+}
+
+/// Prefix of the run record in a transcript.
+pub const RUN_RECORD: &str = "run ";
+
+/// Prefix of a result that was not searched in this run, because it came
+/// from the result cache or a resumed checkpoint.
+pub const RESULT_RECORD: &str = "result ";
+
+/// Log of all engine I/O shared by every worker.
+///
+/// Each line reads `<seconds> w<worker> p<position> <marker> <text>`, where
+/// the seconds are monotonic since the transcript was opened and the
+/// position is the 1-based index being searched (`-` outside a search).
+pub struct Transcript {
+    out: Mutex<BufWriter<File>>,
+    start: Instant,
+}
+
+impl Transcript {
+    pub fn create(path: &str) -> Result<Self> {
+        let file =
+            File::create(path).map_err(|e| anyhow!("Unable to create log file {path}: {e}"))?;
+        Ok(Transcript {
+            out: Mutex::new(BufWriter::new(file)),
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&self, worker: usize, position: Option<usize>, direction: Direction, line: &str) {
+        self.write(&worker.to_string(), position, direction, line);
+    }
+
+    /// Write the run-wide settings record.
+    pub fn record_run(&self, run: &RunInfo) -> Result<()> {
+        let json = serde_json::to_string(run)?;
+        self.write("-", None, Direction::Meta, &format!("{RUN_RECORD}{json}"));
+        Ok(())
+    }
+
+    /// Write a result the engine did not search in this run, so a replay
+    /// still reports every position.
+    pub fn record_result(&self, index: usize, result: &EngineResult) -> Result<()> {
+        let json = serde_json::to_string(&PositionRecord::new(index, result))?;
+        self.write(
+            "-",
+            Some(index),
+            Direction::Meta,
+            &format!("{RESULT_RECORD}{json}"),
+        );
+        Ok(())
+    }
+
+    fn write(&self, worker: &str, position: Option<usize>, direction: Direction, line: &str) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let position = position.map_or("-".to_string(), |p| (p + 1).to_string());
+        if let Ok(mut out) = self.out.lock() {
+            let _ = writeln!(
+                out,
+                "{elapsed:.6} w{worker} p{position} {} {line}",
+                direction.marker()
+            );
+            let _ = out.flush();
+        }
+    }
+}
@@ -1,22 +1,124 @@
+use crate::fens::SuitePosition;
+use crate::transcript::{Direction, Transcript, TranscriptLine};
 use anyhow::{Result, anyhow};
+use std::collections::VecDeque;
 use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, channel};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Number of recent output lines kept for crash reports.
+const RECENT_LINES: usize = 10;
+
+/// Marker for "not searching a position" in `TranscriptLog::position`.
+const NO_POSITION: usize = usize::MAX;
+
+/// Where and under which worker id an engine's I/O is logged.
+#[derive(Clone)]
+struct TranscriptLog {
+    transcript: Arc<Transcript>,
+    worker: usize,
+    /// Position being searched, shared with the stderr reader thread
+    position: Arc<AtomicUsize>,
+}
+
+impl TranscriptLog {
+    fn record(&self, direction: Direction, line: &str) {
+        let position = match self.position.load(Ordering::Relaxed) {
+            NO_POSITION => None,
+            p => Some(p),
+        };
+        self.record_at(position, direction, line);
+    }
+
+    fn record_at(&self, position: Option<usize>, direction: Direction, line: &str) {
+        self.transcript
+            .record(self.worker, position, direction, line);
+    }
+}
+
+/// Where engine output comes from.
+enum Backend {
+    /// A live engine process.
+    Process {
+        engine_path: String,
+        child: Child,
+        stdin: ChildStdin,
+        /// Lines read from the engine's stdout by a background reader
+        /// thread, so reads can time out when the engine hangs.
+        lines: Receiver<String>,
+    },
+    /// Output and events of one worker, read back from a transcript.
+    Replay {
+        events: Vec<TranscriptLine>,
+        cursor: usize,
+    },
+}
 
 pub struct UciEngine {
-    child: Child,
-    stdin: ChildStdin,
-    stdout: BufReader<ChildStdout>,
+    backend: Backend,
+    recent: VecDeque<String>,
+    log: Option<TranscriptLog>,
+}
+
+/// Transcript events recorded for everything that is not engine output but
+/// still decides how a search ends, so a replay takes the same path.
+mod event {
+    pub const TIMEOUT: &str = "timeout";
+    pub const EOF: &str = "eof";
+    pub const EXIT: &str = "exit ";
+    pub const STOPPED: &str = "stopped";
+    pub const KILL: &str = "kill";
+    pub const POSITION: &str = "position ";
 }
 
 impl UciEngine {
     /// Start a new UCI engine process from the given executable path.
-    pub fn start(engine_path: &str) -> Result<Self> {
+    /// With a transcript, its commands, output and stderr are logged there
+    /// under the given worker id.
+    pub fn start(
+        engine_path: &str,
+        transcript: Option<Arc<Transcript>>,
+        worker: usize,
+    ) -> Result<Self> {
+        let log = transcript.map(|transcript| TranscriptLog {
+            transcript,
+            worker,
+            position: Arc::new(AtomicUsize::new(NO_POSITION)),
+        });
+        Ok(UciEngine {
+            backend: Self::spawn(engine_path, log.clone())?,
+            recent: VecDeque::with_capacity(RECENT_LINES),
+            log,
+        })
+    }
+
+    /// An engine that plays back the recorded transcript lines of a single
+    /// worker instead of talking to a process. Commands are discarded.
+    pub fn replay(events: Vec<TranscriptLine>) -> Self {
+        UciEngine {
+            backend: Backend::Replay { events, cursor: 0 },
+            recent: VecDeque::with_capacity(RECENT_LINES),
+            log: None,
+        }
+    }
+
+    fn spawn(engine_path: &str, log: Option<TranscriptLog>) -> Result<Backend> {
+        // Stderr is only captured when there is a transcript to keep it in
+        let stderr = if log.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::inherit()
+        };
         let mut child = Command::new(engine_path)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
+            .stderr(stderr)
             .spawn()
             .map_err(|e| anyhow!("Failed to start engine: {}", e))?;
-
         let stdin = child
             .stdin
             .take()
@@ -25,35 +127,297 @@ impl UciEngine {
             .stdout
             .take()
             .ok_or_else(|| anyhow!("Failed to open stdout"))?;
-        let stdout = BufReader::new(stdout);
 
-        Ok(UciEngine {
+        if let (Some(stderr), Some(log)) = (child.stderr.take(), log.clone()) {
+            thread::spawn(move || {
+                for line in BufReader::new(stderr).lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    log.record(Direction::Stderr, line.trim_end());
+                }
+            });
+        }
+
+        let (tx, lines) = channel();
+        thread::spawn(move || {
+            let mut stdout = BufReader::new(stdout);
+            loop {
+                let mut line = String::new();
+                match stdout.read_line(&mut line) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {
+                        if tx.send(line.trim_end().to_string()).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(Backend::Process {
+            engine_path: engine_path.to_string(),
             child,
             stdin,
-            stdout,
+            lines,
         })
     }
 
+    /// Tag subsequent transcript lines with the position being searched.
+    pub fn set_position(&mut self, index: Option<usize>) {
+        if let Some(log) = &self.log {
+            log.position
+                .store(index.unwrap_or(NO_POSITION), Ordering::Relaxed);
+        }
+    }
+
+    /// Record the position about to be searched, so a replay knows which
+    /// position each part of the transcript belongs to.
+    pub fn record_position(&self, index: usize, position: &SuitePosition) {
+        if let Some(log) = &self.log
+            && let Ok(json) = serde_json::to_string(position)
+        {
+            log.record_at(
+                Some(index),
+                Direction::Meta,
+                &format!("{}{json}", event::POSITION),
+            );
+        }
+    }
+
+    /// In a replay, skip ahead to the next recorded position and return it
+    /// with its index. Always `None` for a live engine.
+    pub fn next_recorded_position(&mut self) -> Option<(usize, SuitePosition)> {
+        let Backend::Replay { events, cursor } = &mut self.backend else {
+            return None;
+        };
+        while let Some(event) = events.get(*cursor) {
+            *cursor += 1;
+            if event.direction == Direction::Meta
+                && let Some(json) = event.text.strip_prefix(event::POSITION)
+                && let (Some(index), Ok(position)) = (event.position, serde_json::from_str(json))
+            {
+                return Some((index, position));
+            }
+        }
+        None
+    }
+
+    /// Kill the current process and start a fresh one from the same binary.
+    /// The caller is responsible for repeating the UCI initialization.
+    pub fn restart(&mut self) -> Result<()> {
+        self.kill();
+        if let Backend::Process { engine_path, .. } = &self.backend {
+            let engine_path = engine_path.clone();
+            self.backend = Self::spawn(&engine_path, self.log.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Kill the engine process and reap it.
+    pub fn kill(&mut self) {
+        self.record_event(event::KILL);
+        match &mut self.backend {
+            Backend::Process { child, .. } => {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            Backend::Replay { .. } => {
+                self.take_event(event::KILL);
+            }
+        }
+    }
+
+    /// Whether the engine process is still alive.
+    pub fn is_running(&mut self) -> bool {
+        let running = match &mut self.backend {
+            Backend::Process { child, .. } => matches!(child.try_wait(), Ok(None)),
+            Backend::Replay { .. } => self.take_event(event::STOPPED).is_none(),
+        };
+        if !running {
+            self.record_event(event::STOPPED);
+        }
+        running
+    }
+
+    /// Wait up to `timeout` for the process to exit and describe how it
+    /// ended (exit code or signal). Returns `None` if it is still running.
+    pub fn wait_exit(&mut self, timeout: Duration) -> Option<String> {
+        let status = match &mut self.backend {
+            Backend::Process { child, .. } => {
+                let deadline = Instant::now() + timeout;
+                loop {
+                    match child.try_wait() {
+                        Ok(Some(status)) => break Some(status.to_string()),
+                        Ok(None) if Instant::now() < deadline => {
+                            thread::sleep(Duration::from_millis(10))
+                        }
+                        Ok(None) => break None,
+                        Err(e) => break Some(format!("unknown ({e})")),
+                    }
+                }
+            }
+            Backend::Replay { .. } => self.take_event(event::EXIT),
+        };
+        if let Some(status) = &status {
+            self.record_event(&format!("{}{status}", event::EXIT));
+        }
+        status
+    }
+
+    fn record_event(&self, text: &str) {
+        if let Some(log) = &self.log {
+            log.record(Direction::Meta, text);
+        }
+    }
+
+    /// In a replay, consume the next recorded event if it starts with
+    /// `prefix` and return the rest of it. Commands and stderr are skipped.
+    fn take_event(&mut self, prefix: &str) -> Option<String> {
+        let Backend::Replay { events, cursor } = &mut self.backend else {
+            return None;
+        };
+        while let Some(event) = events.get(*cursor) {
+            match event.direction {
+                Direction::ToEngine | Direction::Stderr => *cursor += 1,
+                Direction::Meta if event.text == event::KILL && prefix != event::KILL => {
+                    *cursor += 1
+                }
+                _ => break,
+            }
+        }
+        let event = events.get(*cursor)?;
+        let rest = event.text.strip_prefix(prefix)?;
+        if event.direction != Direction::Meta {
+            return None;
+        }
+        *cursor += 1;
+        Some(rest.to_string())
+    }
+
+    /// Next recorded output line of a replay: `Ok(Some)` for engine output,
+    /// `Ok(None)` where the live read timed out, an error where the output
+    /// was closed or the recording ends.
+    fn next_replayed_line(&mut self) -> Result<Option<String>> {
+        if self.take_event(event::TIMEOUT).is_some() {
+            return Ok(None);
+        }
+        if self.take_event(event::EOF).is_some() {
+            return Err(anyhow!("Engine process closed output"));
+        }
+        let Backend::Replay { events, cursor } = &mut self.backend else {
+            return Err(anyhow!("Not a replay"));
+        };
+        match events.get(*cursor) {
+            Some(event) if event.direction == Direction::FromEngine => {
+                *cursor += 1;
+                Ok(Some(event.text.clone()))
+            }
+            Some(event) => Err(anyhow!(
+                "Transcript out of sync: expected engine output, found '{} {}'",
+                event.direction.marker(),
+                event.text
+            )),
+            None => Err(anyhow!("Engine process closed output")),
+        }
+    }
+
+    /// The last lines the engine printed, oldest first.
+    pub fn recent_output(&self) -> Vec<String> {
+        self.recent.iter().cloned().collect()
+    }
+
+    fn remember(&mut self, line: &str) {
+        if let Some(log) = &self.log {
+            log.record(Direction::FromEngine, line);
+        }
+        if self.recent.len() == RECENT_LINES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(line.to_string());
+    }
+
     /// Send a raw UCI command string (for compatibility).
     pub fn send_command(&mut self, command: &str) -> Result<()> {
-        writeln!(self.stdin, "{}", command)?;
-        self.stdin.flush()?;
+        if let Some(log) = &self.log {
+            log.record(Direction::ToEngine, command);
+        }
+        if let Backend::Process { stdin, .. } = &mut self.backend {
+            writeln!(stdin, "{}", command)?;
+            stdin.flush()?;
+        }
         Ok(())
     }
 
+    /// Send `isready` and wait for `readyok`, discarding anything printed in
+    /// between. Returns `Ok(false)` if `timeout` expires first.
+    pub fn sync(&mut self, timeout: Option<Duration>) -> Result<bool> {
+        self.send_command("isready")?;
+        let deadline = timeout.map(|t| Instant::now() + t);
+        loop {
+            let line = match deadline {
+                None => self.read_line()?,
+                Some(at) => {
+                    match self.read_line_timeout(at.saturating_duration_since(Instant::now()))? {
+                        Some(line) => line,
+                        None => return Ok(false),
+                    }
+                }
+            };
+            if line == "readyok" {
+                return Ok(true);
+            }
+        }
+    }
+
     /// Read a line of output from the engine as a raw string.
     pub fn read_line(&mut self) -> Result<String> {
-        let mut line = String::new();
-        let n = self.stdout.read_line(&mut line)?;
-        if n == 0 {
+        let line = match &self.backend {
+            Backend::Process { lines, .. } => lines.recv().ok(),
+            Backend::Replay { .. } => match self.next_replayed_line()? {
+                Some(line) => Some(line),
+                None => return Err(anyhow!("Transcript has a timeout in a blocking read")),
+            },
+        };
+        let Some(line) = line else {
+            self.record_event(event::EOF);
             return Err(anyhow!("Engine process closed output"));
+        };
+        self.remember(&line);
+        Ok(line)
+    }
+
+    /// Read a line of output, giving up after `timeout`. Returns `Ok(None)`
+    /// when no line arrived in time.
+    pub fn read_line_timeout(&mut self, timeout: Duration) -> Result<Option<String>> {
+        let received = match &self.backend {
+            Backend::Process { lines, .. } => lines.recv_timeout(timeout),
+            Backend::Replay { .. } => match self.next_replayed_line()? {
+                Some(line) => Ok(line),
+                None => Err(RecvTimeoutError::Timeout),
+            },
+        };
+        match received {
+            Ok(line) => {
+                self.remember(&line);
+                Ok(Some(line))
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                self.record_event(event::TIMEOUT);
+                Ok(None)
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                self.record_event(event::EOF);
+                Err(anyhow!("Engine process closed output"))
+            }
         }
-        Ok(line.trim_end().to_string())
     }
 }
 
 impl Drop for UciEngine {
     fn drop(&mut self) {
-        let _ = self.child.kill();
+        if let Backend::Process { child, .. } = &mut self.backend {
+            let _ = child.kill();
+        }
     }
 }
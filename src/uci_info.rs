@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use shakmaty::uci::UciMove;
+use std::fmt;
+use std::str::FromStr;
+
+/// Error raised for an `info` line that does not follow the UCI grammar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UciParseError {
+    /// The line does not start with `info`.
+    NotInfo,
+    /// A field was given without its value.
+    MissingValue(&'static str),
+    /// A numeric field whose value does not parse.
+    InvalidNumber { field: &'static str, value: String },
+    /// `score` followed by something other than `cp` or `mate`.
+    InvalidScore(String),
+    /// A field expecting a move in UCI notation got something else.
+    InvalidMove { field: &'static str, value: String },
+}
+
+impl fmt::Display for UciParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UciParseError::NotInfo => write!(f, "not an info line"),
+            UciParseError::MissingValue(field) => write!(f, "missing value for '{field}'"),
+            UciParseError::InvalidNumber { field, value } => {
+                write!(f, "invalid number '{value}' for '{field}'")
+            }
+            UciParseError::InvalidScore(kind) => write!(f, "invalid score type '{kind}'"),
+            UciParseError::InvalidMove { field, value } => {
+                write!(f, "invalid move '{value}' for '{field}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UciParseError {}
+
+/// Value part of a `score` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreValue {
+    Cp(i32),
+    Mate(i32),
+}
+
+/// Whether a score is exact or only a bound from a failed aspiration search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScoreBound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Score {
+    pub value: ScoreValue,
+    pub bound: ScoreBound,
+}
+
+impl Score {
+    pub fn is_exact(&self) -> bool {
+        self.bound == ScoreBound::Exact
+    }
+
+    /// Mate distance when this is an exact mate score.
+    pub fn exact_mate(&self) -> Option<i32> {
+        match self.value {
+            ScoreValue::Mate(n) if self.is_exact() => Some(n),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            ScoreValue::Cp(cp) => write!(f, "cp {cp}")?,
+            ScoreValue::Mate(n) => write!(f, "mate {n}")?,
+        }
+        match self.bound {
+            ScoreBound::Exact => Ok(()),
+            ScoreBound::Lower => write!(f, " lowerbound"),
+            ScoreBound::Upper => write!(f, " upperbound"),
+        }
+    }
+}
+
+/// Every field of a UCI `info` line. Move lists are kept in UCI notation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InfoLine {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub time_ms: Option<u64>,
+    pub nodes: Option<u64>,
+    pub pv: Vec<String>,
+    pub multipv: Option<u32>,
+    pub score: Option<Score>,
+    pub wdl: Option<(u32, u32, u32)>,
+    pub currmove: Option<String>,
+    pub currmovenumber: Option<u32>,
+    pub hashfull: Option<u32>,
+    pub nps: Option<u64>,
+    pub tbhits: Option<u64>,
+    pub sbhits: Option<u64>,
+    pub cpuload: Option<u32>,
+    pub string: Option<String>,
+    pub refutation: Vec<String>,
+    pub currline: Vec<String>,
+    /// Tokens that are not standard `info` fields, such as Lc0's
+    /// `movesleft 30`, in the order they were sent
+    pub unknown: Vec<String>,
+}
+
+impl InfoLine {
+    /// Parse a full `info ...` line as sent by the engine.
+    pub fn parse(line: &str) -> Result<InfoLine, UciParseError> {
+        let mut tokens = Tokens::new(line);
+        if tokens.next() != Some("info") {
+            return Err(UciParseError::NotInfo);
+        }
+
+        let mut info = InfoLine::default();
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => info.depth = Some(tokens.number("depth")?),
+                "seldepth" => info.seldepth = Some(tokens.number("seldepth")?),
+                "time" => info.time_ms = Some(tokens.number("time")?),
+                "nodes" => info.nodes = Some(tokens.number("nodes")?),
+                "multipv" => info.multipv = Some(tokens.number("multipv")?),
+                "currmovenumber" => info.currmovenumber = Some(tokens.number("currmovenumber")?),
+                "hashfull" => info.hashfull = Some(tokens.number("hashfull")?),
+                "nps" => info.nps = Some(tokens.number("nps")?),
+                "tbhits" => info.tbhits = Some(tokens.number("tbhits")?),
+                "sbhits" => info.sbhits = Some(tokens.number("sbhits")?),
+                "cpuload" => info.cpuload = Some(tokens.number("cpuload")?),
+                "score" => info.score = Some(tokens.score()?),
+                "wdl" => {
+                    info.wdl = Some((
+                        tokens.number("wdl")?,
+                        tokens.number("wdl")?,
+                        tokens.number("wdl")?,
+                    ))
+                }
+                "currmove" => info.currmove = Some(tokens.uci_move("currmove")?),
+                "pv" => info.pv = tokens.moves(),
+                "refutation" => info.refutation = tokens.moves(),
+                "currline" => {
+                    // Optional cpu number before the moves
+                    if tokens.peek().is_some_and(|t| t.parse::<u32>().is_ok()) {
+                        tokens.next();
+                    }
+                    info.currline = tokens.moves();
+                }
+                "string" => {
+                    // `string` consumes the rest of the line
+                    info.string = Some(tokens.rest());
+                }
+                other => info.unknown.push(other.to_string()),
+            }
+        }
+        Ok(info)
+    }
+}
+
+/// Whitespace tokenizer with the lookups the `info` grammar needs.
+struct Tokens<'a> {
+    parts: Vec<&'a str>,
+    idx: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(line: &'a str) -> Self {
+        Tokens {
+            parts: line.split_whitespace().collect(),
+            idx: 0,
+        }
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.parts.get(self.idx).copied();
+        if token.is_some() {
+            self.idx += 1;
+        }
+        token
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.parts.get(self.idx).copied()
+    }
+
+    fn value(&mut self, field: &'static str) -> Result<&'a str, UciParseError> {
+        self.next().ok_or(UciParseError::MissingValue(field))
+    }
+
+    fn number<T: FromStr>(&mut self, field: &'static str) -> Result<T, UciParseError> {
+        let value = self.value(field)?;
+        value.parse().map_err(|_| UciParseError::InvalidNumber {
+            field,
+            value: value.to_string(),
+        })
+    }
+
+    fn uci_move(&mut self, field: &'static str) -> Result<String, UciParseError> {
+        let value = self.value(field)?;
+        UciMove::from_ascii(value.as_bytes())
+            .map(|_| value.to_string())
+            .map_err(|_| UciParseError::InvalidMove {
+                field,
+                value: value.to_string(),
+            })
+    }
+
+    /// Consume tokens for as long as they are moves in UCI notation.
+    fn moves(&mut self) -> Vec<String> {
+        let mut moves = Vec::new();
+        while let Some(token) = self.peek() {
+            if UciMove::from_ascii(token.as_bytes()).is_err() {
+                break;
+            }
+            moves.push(token.to_string());
+            self.idx += 1;
+        }
+        moves
+    }
+
+    fn score(&mut self) -> Result<Score, UciParseError> {
+        let value = match self.value("score")? {
+            "cp" => ScoreValue::Cp(self.number("score cp")?),
+            "mate" => ScoreValue::Mate(self.number("score mate")?),
+            other => return Err(UciParseError::InvalidScore(other.to_string())),
+        };
+        let bound = match self.peek() {
+            Some("lowerbound") => ScoreBound::Lower,
+            Some("upperbound") => ScoreBound::Upper,
+            _ => ScoreBound::Exact,
+        };
+        if bound != ScoreBound::Exact {
+            self.idx += 1;
+        }
+        Ok(Score { value, bound })
+    }
+
+    fn rest(&mut self) -> String {
+        let rest = self.parts[self.idx..].join(" ");
+        self.idx = self.parts.len();
+        rest
+    }
+}
@@ -0,0 +1,82 @@
+//! Parsing `option` lines from the handshake and validating settings against them.
+
+use engine_mate_stats::engine_options::{EngineOption, OptionSetting, OptionType};
+
+#[test]
+fn option_names_may_contain_spaces() {
+    let option = EngineOption::parse("option name Clear Hash type button").unwrap();
+    assert_eq!(option.name, "Clear Hash");
+    assert_eq!(option.kind, OptionType::Button);
+
+    let option =
+        EngineOption::parse("option name Skill Level type spin default 20 min 0 max 20").unwrap();
+    assert_eq!(option.name, "Skill Level");
+    assert_eq!(option.kind, OptionType::Spin { min: 0, max: 20 });
+}
+
+#[test]
+fn combos_collect_every_var() {
+    let option = EngineOption::parse(
+        "option name Style type combo default Normal var Solid var Very Risky var Normal",
+    )
+    .unwrap();
+    assert_eq!(
+        option.kind,
+        OptionType::Combo {
+            vars: vec![
+                "Solid".to_string(),
+                "Very Risky".to_string(),
+                "Normal".to_string()
+            ]
+        }
+    );
+    assert!(
+        option
+            .validate(&OptionSetting::new("Style", "very risky"))
+            .is_ok()
+    );
+    assert!(
+        option
+            .validate(&OptionSetting::new("Style", "Risky"))
+            .is_err()
+    );
+}
+
+#[test]
+fn spins_without_bounds_accept_any_integer() {
+    let option = EngineOption::parse("option name Contempt type spin default 0").unwrap();
+    assert_eq!(
+        option.kind,
+        OptionType::Spin {
+            min: i64::MIN,
+            max: i64::MAX
+        }
+    );
+    assert!(
+        option
+            .validate(&OptionSetting::new("Contempt", -100))
+            .is_ok()
+    );
+    assert!(
+        option
+            .validate(&OptionSetting::new("Contempt", "x"))
+            .is_err()
+    );
+
+    let option = EngineOption::parse("option name Hash type spin default 16 min 1").unwrap();
+    assert_eq!(
+        option.kind,
+        OptionType::Spin {
+            min: 1,
+            max: i64::MAX
+        }
+    );
+}
+
+#[test]
+fn malformed_lines_are_not_options() {
+    assert_eq!(EngineOption::parse("id name Mock"), None);
+    assert_eq!(EngineOption::parse("option name Hash"), None);
+    assert_eq!(EngineOption::parse("option type check"), None);
+    assert_eq!(EngineOption::parse("option name Foo type widget"), None);
+}
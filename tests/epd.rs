@@ -0,0 +1,66 @@
+//! EPD parsing, including normalization of expected best moves.
+
+use engine_mate_stats::epd::{format_epd_line, parse_epd_line};
+
+#[test]
+fn counters_and_mate_distance() {
+    let position = parse_epd_line(
+        "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1; hmvc 3; fmvn 40;",
+        "s.epd",
+    )
+    .unwrap();
+    assert_eq!(position.fen, "7k/5K2/6Q1/8/8/8/8/8 w - - 3 40");
+    assert_eq!(position.expected_mate, Some(1));
+    assert!(position.best_moves.is_empty());
+}
+
+#[test]
+fn best_moves_are_normalized_to_uci() {
+    let san = parse_epd_line("7k/5K2/6Q1/8/8/8/8/8 w - - bm Qg7#;", "s.epd").unwrap();
+    let uci = parse_epd_line("7k/5K2/6Q1/8/8/8/8/8 w - - bm g6g7;", "s.epd").unwrap();
+    assert_eq!(san.best_moves, vec!["g6g7"]);
+    assert_eq!(uci.best_moves, san.best_moves);
+
+    let several = parse_epd_line(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4 Nf3;",
+        "s.epd",
+    )
+    .unwrap();
+    assert_eq!(several.best_moves, vec!["e2e4", "g1f3"]);
+
+    let castling = parse_epd_line("4k3/8/8/8/8/8/8/4K2R w K - bm O-O;", "s.epd").unwrap();
+    assert_eq!(castling.best_moves, vec!["e1g1"]);
+}
+
+#[test]
+fn mating_line_supplies_the_first_move() {
+    let position = parse_epd_line("7k/5K2/6Q1/8/8/8/8/8 w - - bm #1; PV: g6g7;", "s.epd").unwrap();
+    assert_eq!(position.expected_mate, Some(1));
+    assert_eq!(position.best_moves, vec!["g6g7"]);
+
+    let bm_wins = parse_epd_line("7k/5K2/6Q1/8/8/8/8/8 w - - bm Qh7#; pv g6g7;", "s.epd");
+    // Qh7 is not mate here, but `bm` is what the suite claims
+    assert_eq!(bm_wins.unwrap().best_moves, vec!["g6h7"]);
+}
+
+#[test]
+fn illegal_best_move_is_rejected() {
+    assert!(parse_epd_line("7k/5K2/6Q1/8/8/8/8/8 w - - bm Qa1b2;", "s.epd").is_err());
+    assert!(parse_epd_line("7k/5K2/6Q1/8/8/8/8/8 w - - bm e2e4;", "s.epd").is_err());
+}
+
+#[test]
+fn formatted_records_parse_back_to_the_same_position() {
+    for line in [
+        "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1 Qg7#;",
+        "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - bm O-O; hmvc 3; fmvn 12;",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+    ] {
+        let position = parse_epd_line(line, "s.epd").unwrap();
+        assert_eq!(format_epd_line(&position), line);
+        let again = parse_epd_line(&format_epd_line(&position), "s.epd").unwrap();
+        assert_eq!(again.fen, position.fen);
+        assert_eq!(again.expected_mate, position.expected_mate);
+        assert_eq!(again.best_moves, position.best_moves);
+    }
+}
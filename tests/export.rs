@@ -0,0 +1,46 @@
+//! Exported position records must rebuild the results they were made from.
+
+use engine_mate_stats::export::PositionRecord;
+use engine_mate_stats::{EngineResult, InfoLine, ResultStatus, epd, pv_check};
+
+fn result(epd_line: &str, info: &str, status: ResultStatus) -> EngineResult {
+    let position = epd::parse_epd_line(epd_line, "a.epd").unwrap();
+    let mut result = EngineResult::new(&position);
+    result.apply_info(&InfoLine::parse(info).unwrap());
+    result.status = status;
+    result
+}
+
+#[test]
+fn pv_issues_survive_the_round_trip_for_every_status() {
+    for status in [
+        ResultStatus::Completed,
+        ResultStatus::TimedOut,
+        ResultStatus::Crashed {
+            exit_status: "signal 11".to_string(),
+            last_output: vec!["info depth 2".to_string()],
+        },
+    ] {
+        // Found after `stop` or before the crash: the illegal PV was checked
+        let mut checked = result(
+            "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1;",
+            "info depth 2 score mate 1 pv g6a1",
+            status.clone(),
+        );
+        checked.bestmove = "g6a1".to_string();
+        checked.pv_issue = pv_check::verify_pv(&checked.fen, &checked.pv, checked.mate());
+        assert!(checked.pv_issue.is_some());
+        let rebuilt = PositionRecord::new(0, &checked).to_result();
+        assert_eq!(rebuilt.pv_issue, checked.pv_issue, "{status:?}");
+        assert_eq!(rebuilt.status, status);
+
+        // Killed before `bestmove`: the PV was never checked
+        let unchecked = result(
+            "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1;",
+            "info depth 2 score mate 1 pv g6a1",
+            status.clone(),
+        );
+        let rebuilt = PositionRecord::new(0, &unchecked).to_result();
+        assert_eq!(rebuilt.pv_issue, None, "{status:?}");
+    }
+}
@@ -0,0 +1,71 @@
+//! Validation and normalization of positions while a suite is loaded.
+
+use engine_mate_stats::fens::{Fens, SkipReason};
+
+fn load(lines: &[&str]) -> Fens {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("suite.epd");
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    Fens::load_epd(path.to_str().unwrap()).unwrap()
+}
+
+#[test]
+fn invalid_and_decided_positions_are_skipped_with_their_line() {
+    let mut fens = load(&[
+        "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1;",
+        "# opposite check, then impossible castling and en passant",
+        "7k/6Q1/5K2/8/8/8/8/8 w - -",
+        "7k/8/8/8/8/8/8/K7 w KQ -",
+        "7k/8/8/8/8/8/8/K7 w - e6",
+        "not a position",
+        "7k/6Q1/5K2/8/8/8/8/8 b - -",
+        "7k/5Q2/6K1/8/8/8/8/8 b - -",
+    ]);
+
+    let skipped: Vec<_> = fens.skipped().iter().map(|p| p.index).collect();
+    assert_eq!(skipped, vec![3, 4, 5, 6, 7, 8]);
+    assert_eq!(fens.invalid().count(), 4);
+    assert!(matches!(&fens.skipped()[0].reason, SkipReason::Invalid(r) if r.contains("check")));
+    assert_eq!(fens.skipped()[4].reason, SkipReason::Checkmate);
+    assert_eq!(fens.skipped()[5].reason, SkipReason::Stalemate);
+    assert!(
+        fens.skipped()[0]
+            .to_string()
+            .contains(":3: invalid position")
+    );
+
+    let positions = fens.take_positions(10);
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].expected_mate, Some(1));
+}
+
+#[test]
+fn fens_are_normalized() {
+    let mut fens = load(&["rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3"]);
+    assert!(fens.skipped().is_empty());
+    assert_eq!(
+        fens.take_positions(1)[0].fen,
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+    );
+}
+
+#[test]
+fn embedded_positions_are_all_valid() {
+    assert!(Fens::load_fens().unwrap().skipped().is_empty());
+}
+
+#[test]
+fn duplicates_differing_only_in_counters_are_dropped() {
+    let mut fens = load(&[
+        "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1;",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq -",
+        "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1; hmvc 12; fmvn 40;",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3",
+        "7k/8/5K2/6Q1/8/8/8/8 b - -",
+        "7k/8/5K2/6Q1/8/8/8/8 w - -",
+    ]);
+    assert_eq!(fens.dedup(), 2);
+    let positions = fens.take_positions(10);
+    assert_eq!(positions.len(), 4);
+    assert_eq!(positions[0].fen, "7k/5K2/6Q1/8/8/8/8/8 w - - 0 1");
+}
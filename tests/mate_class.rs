@@ -0,0 +1,51 @@
+//! Classifying reported mates against the expected mate.
+
+use engine_mate_stats::mate_class::{MateClass, MateClassCounts};
+use engine_mate_stats::{EngineResult, InfoLine, SuitePosition};
+
+#[test]
+fn mates_are_classified_by_sign_and_distance() {
+    assert_eq!(MateClass::classify(3, Some(3)), MateClass::Best);
+    assert_eq!(MateClass::classify(-3, Some(-3)), MateClass::Best);
+    assert_eq!(MateClass::classify(3, Some(5)), MateClass::Longer);
+    assert_eq!(MateClass::classify(-3, Some(-7)), MateClass::Longer);
+    assert_eq!(MateClass::classify(3, None), MateClass::NotFound);
+}
+
+#[test]
+fn wrong_sign_wins_over_distance() {
+    assert_eq!(MateClass::classify(3, Some(-3)), MateClass::WrongSign);
+    assert_eq!(MateClass::classify(-2, Some(1)), MateClass::WrongSign);
+    assert_eq!(MateClass::classify(-2, Some(9)), MateClass::WrongSign);
+}
+
+#[test]
+fn mates_shorter_than_proven_are_impossible() {
+    assert_eq!(MateClass::classify(5, Some(4)), MateClass::Impossible);
+    assert_eq!(MateClass::classify(5, Some(1)), MateClass::Impossible);
+    assert_eq!(MateClass::classify(-5, Some(-2)), MateClass::Impossible);
+
+    let mut counts = MateClassCounts::default();
+    for class in [MateClass::Best, MateClass::Longer, MateClass::Impossible] {
+        counts.add(class);
+    }
+    counts.add(MateClass::WrongSign);
+    assert_eq!(counts.found(), 3);
+}
+
+#[test]
+fn positions_without_an_expected_mate_are_not_classified() {
+    let position = SuitePosition {
+        fen: "7k/5K2/6Q1/8/8/8/8/8 w - - 0 1".to_string(),
+        source: "a.epd".to_string(),
+        expected_mate: None,
+        best_moves: Vec::new(),
+    };
+    let mut result = EngineResult::new(&position);
+    result.apply_info(&InfoLine::parse("info depth 3 score mate 1 pv g6g7").unwrap());
+    assert_eq!(result.mate(), Some(1));
+    assert_eq!(result.mate_class(), None);
+
+    result.expected_mate = Some(1);
+    assert_eq!(result.mate_class(), Some(MateClass::Best));
+}
@@ -0,0 +1,62 @@
+//! Drives the library API directly against the scripted mock engine.
+
+use engine_mate_stats::{MateRun, OptionSetting, ResultStatus, SearchLimits, epd};
+
+const MATE_IN_1: &str = "7k/5K2/6Q1/8/8/8/8/8 w - -";
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+
+// A single test, because the script is handed to the engine through the
+// environment of this process.
+#[test]
+fn mate_run_returns_structured_results() {
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("script.json");
+    let json = serde_json::json!({
+        "name": "Mock 2.0",
+        "positions": [{
+            "fen": MATE_IN_1,
+            "output": ["info depth 1 score mate 1 nodes 42 pv g6g7", "bestmove g6g7"]
+        }],
+        "default": {
+            "output": ["info depth 4 score cp 15 nodes 900 pv e2e4", "bestmove e2e4"]
+        }
+    });
+    std::fs::write(&script, json.to_string()).unwrap();
+    // SAFETY: no other thread of this test binary reads the environment yet
+    unsafe { std::env::set_var("MOCK_UCI_SCRIPT", &script) };
+
+    let positions = vec![
+        epd::parse_epd_line(&format!("{MATE_IN_1} bm #1;"), "inline").unwrap(),
+        epd::parse_epd_line(START, "inline").unwrap(),
+    ];
+    let run = MateRun::new(env!("CARGO_BIN_EXE_mock_uci_engine"))
+        .threads(2)
+        .option(OptionSetting::new("Hash", 32))
+        .limits(SearchLimits::new(Some(1000), None, None, None))
+        .workers(2)
+        .positions(positions);
+    let output = run.run().unwrap();
+
+    assert_eq!(output.engine_name, "Mock 2.0");
+    assert_eq!(output.results.len(), 2);
+    assert_eq!(output.results[0].mate(), Some(1));
+    assert_eq!(output.results[0].bestmove, "g6g7");
+    assert_eq!(output.results[1].nodes, 900);
+    assert!(
+        output
+            .results
+            .iter()
+            .all(|r| r.status == ResultStatus::Completed)
+    );
+
+    let stats = output.analyzer().summary();
+    assert_eq!(stats.positions_analyzed, 2);
+    assert_eq!(stats.mate_classes.best, 1);
+    assert_eq!(stats.mates_in(1), 1);
+
+    let rejected = MateRun::new(env!("CARGO_BIN_EXE_mock_uci_engine"))
+        .option(OptionSetting::new("Hash", 0))
+        .positions(vec![epd::parse_epd_line(START, "inline").unwrap()])
+        .run();
+    assert!(rejected.is_err());
+}
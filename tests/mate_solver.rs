@@ -0,0 +1,86 @@
+//! The built-in mate solver, and how it annotates positions and checks
+//! engine mate scores.
+
+use engine_mate_stats::fens::SuitePosition;
+use engine_mate_stats::mate_solver::{self, MateSolver, Solution};
+use engine_mate_stats::{EngineResult, InfoLine, notation};
+
+const MATE_IN_1: &str = "7k/5K2/6Q1/8/8/8/8/8 w - - 0 1";
+/// Morphy: 1. Ra6 bxa6 2. b7#
+const MATE_IN_2: &str = "kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1";
+/// Black's only move 1... Kb8 allows 2. Rh8#
+const MATED_IN_1: &str = "k7/8/1K6/8/8/8/8/7R b - - 0 1";
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn solve(fen: &str, max_moves: u32) -> Solution {
+    MateSolver::default().solve(&notation::position(fen).unwrap(), max_moves)
+}
+
+fn suite_position(fen: &str, expected_mate: Option<i32>) -> SuitePosition {
+    SuitePosition {
+        fen: fen.to_string(),
+        source: "s.epd".to_string(),
+        expected_mate,
+        best_moves: Vec::new(),
+    }
+}
+
+#[test]
+fn shortest_mates_are_found_for_either_side() {
+    assert_eq!(solve(MATE_IN_1, 3), Solution::Mate(1));
+    assert_eq!(solve(MATE_IN_2, 3), Solution::Mate(2));
+    assert_eq!(solve(MATE_IN_2, 1), Solution::NoMate);
+    assert_eq!(solve(MATED_IN_1, 2), Solution::Mate(-1));
+    assert_eq!(solve(START, 2), Solution::NoMate);
+
+    let mut solver = MateSolver::new(50);
+    let pos = notation::position(MATE_IN_2).unwrap();
+    assert_eq!(solver.solve(&pos, 2), Solution::Unknown);
+    assert!(solver.nodes() > 50);
+}
+
+#[test]
+fn annotations_are_supplied_corrected_or_dropped() {
+    let annotate = |expected| {
+        let mut position = suite_position(MATE_IN_2, expected);
+        mate_solver::annotate(&mut position, 3);
+        position.expected_mate
+    };
+    assert_eq!(annotate(None), Some(2));
+    assert_eq!(annotate(Some(2)), Some(2));
+    // A longer annotation is replaced by the proven shortest mate
+    assert_eq!(annotate(Some(5)), Some(2));
+    // A mate in 1 does not exist, but the mate in 2 found instead is kept
+    assert_eq!(annotate(Some(1)), Some(2));
+    assert_eq!(annotate(Some(-1)), Some(2));
+
+    // No mate within reach at all: the annotation is dropped
+    let mut refuted = suite_position(START, Some(1));
+    mate_solver::annotate(&mut refuted, 2);
+    assert_eq!(refuted.expected_mate, None);
+    // Too long to search, so it is left alone
+    let mut long = suite_position(START, Some(5));
+    mate_solver::annotate(&mut long, 2);
+    assert_eq!(long.expected_mate, Some(5));
+
+    let mut quiet = suite_position(START, None);
+    mate_solver::annotate(&mut quiet, 2);
+    assert_eq!(quiet.expected_mate, None);
+}
+
+#[test]
+fn engine_mate_claims_are_confirmed_or_refuted() {
+    let check = |info: &str, max_moves| {
+        let mut result = EngineResult::new(&suite_position(MATE_IN_2, Some(2)));
+        result.apply_info(&InfoLine::parse(info).unwrap());
+        mate_solver::check_claim(&mut result, max_moves);
+        result.mate_verified
+    };
+    assert_eq!(check("info depth 4 score mate 2 pv a1a6", 3), Some(true));
+    assert_eq!(check("info depth 4 score mate 3 pv a1a6", 3), Some(true));
+    assert_eq!(check("info depth 4 score mate 1 pv b6b7", 3), Some(false));
+    assert_eq!(check("info depth 4 score mate -2 pv a1a6", 3), Some(false));
+    // Too long to check, or no mate score at all
+    assert_eq!(check("info depth 4 score mate 2 pv a1a6", 1), None);
+    assert_eq!(check("info depth 4 score cp 500 pv a1a6", 3), None);
+}
@@ -0,0 +1,868 @@
+//! Runs `engine_mate_stats` against the scripted `mock_uci_engine` and checks
+//! the report and the CSV it writes.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const MATE_IN_1: &str = "7k/5K2/6Q1/8/8/8/8/8 w - -";
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+const KINGS: &str = "8/8/8/8/8/8/8/K6k w - -";
+
+struct Fixture {
+    dir: tempfile::TempDir,
+}
+
+impl Fixture {
+    fn new(script: serde_json::Value) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("script.json"), script.to_string()).unwrap();
+        Fixture { dir }
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.path().join(name)
+    }
+
+    fn write_epd(&self, lines: &[&str]) -> PathBuf {
+        let path = self.path("suite.epd");
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    /// Run against the mock on `suite.epd`, writing `results.csv`.
+    fn run(&self, args: &[&str]) -> Output {
+        self.run_engine(Path::new(env!("CARGO_BIN_EXE_mock_uci_engine")), args)
+    }
+
+    fn run_engine(&self, engine: &Path, args: &[&str]) -> Output {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_engine_mate_stats"));
+        command
+            .env("MOCK_UCI_SCRIPT", self.path("script.json"))
+            .arg("-e")
+            .arg(engine)
+            .arg("--epdFile")
+            .arg(self.path("suite.epd"))
+            .arg("-o")
+            .arg(self.path("results.csv"))
+            .arg("--cacheDir")
+            .arg(self.path("cache"))
+            .args(["--threads", "1"])
+            .args(args);
+        command.output().unwrap()
+    }
+
+    fn csv_rows(&self) -> Vec<HashMap<String, String>> {
+        read_csv(&self.path("results.csv"))
+    }
+}
+
+fn read_csv(path: &Path) -> Vec<HashMap<String, String>> {
+    let mut reader = csv::Reader::from_path(path).unwrap();
+    let headers = reader.headers().unwrap().clone();
+    reader
+        .records()
+        .map(|record| {
+            let record = record.unwrap();
+            headers
+                .iter()
+                .zip(record.iter())
+                .map(|(h, v)| (h.to_string(), v.to_string()))
+                .collect()
+        })
+        .collect()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn mate_in_1(behavior: &str, output: &[&str]) -> serde_json::Value {
+    serde_json::json!({ "fen": MATE_IN_1, "behavior": behavior, "output": output })
+}
+
+fn quiet_default() -> serde_json::Value {
+    serde_json::json!({
+        "output": [
+            "info depth 5 seldepth 7 score cp 20 nodes 2000 nps 100000 time 20 pv e2e4",
+            "bestmove e2e4"
+        ]
+    })
+}
+
+#[test]
+fn handshake_reports_engine_name_and_searches_every_position() {
+    let fixture = Fixture::new(serde_json::json!({
+        "name": "Mock 1.0",
+        "positions": [mate_in_1("normal", &[
+            "info depth 3 seldepth 3 score mate 1 nodes 100 nps 10000 time 10 pv g6g7",
+            "bestmove g6g7"
+        ])],
+        "default": quiet_default()
+    }));
+    fixture.write_epd(&[
+        &format!("{MATE_IN_1} bm #1;"),
+        &format!("{START} hmvc 0; fmvn 1;"),
+    ]);
+
+    let output = fixture.run(&[]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(stdout(&output).contains("Analysis for engine: Mock 1.0"));
+
+    let rows = fixture.csv_rows();
+    let all = &rows[0];
+    assert_eq!(all["engine_name"], "Mock 1.0");
+    assert_eq!(all["source"], "all");
+    assert_eq!(all["positions_analyzed"], "2");
+    assert_eq!(all["max_nodes"], "2000");
+    assert_eq!(all["min_nodes"], "100");
+    assert_eq!(all["expected_mates"], "1");
+    assert_eq!(all["best_mates"], "1");
+    assert_eq!(all["bad_mate_pvs"], "0");
+    assert_eq!(all["timed_out"], "0");
+    assert_eq!(all["crashed"], "0");
+}
+
+#[test]
+fn options_are_validated_against_the_advertised_ones() {
+    let fixture = Fixture::new(serde_json::json!({ "default": quiet_default() }));
+    fixture.write_epd(&[START]);
+
+    let output = fixture.run(&["--hash", "64", "--engineOpts", r#"{"Style": "Risky"}"#]);
+    assert!(output.status.success());
+
+    let output = fixture.run(&["--engineOpts", r#"{"Style": "Reckless"}"#]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Style"));
+
+    let output = fixture.run(&["--syzygyPath", "/tb"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("SyzygyPath"));
+}
+
+#[test]
+fn engine_opts_are_sent_in_the_order_given() {
+    let fixture = Fixture::new(serde_json::json!({ "default": quiet_default() }));
+    fixture.write_epd(&[START]);
+
+    let log = fixture.path("run.log");
+    let output = fixture.run(&[
+        "--engineOpts",
+        r#"{"Style": "Risky", "Hash": 32}"#,
+        "--logFile",
+        log.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    let transcript = std::fs::read_to_string(&log).unwrap();
+    let style = transcript.find("setoption name Style").unwrap();
+    let hash = transcript.find("setoption name Hash").unwrap();
+    assert!(style < hash);
+}
+
+#[test]
+fn threads_is_sent_even_when_not_advertised() {
+    let fixture = Fixture::new(serde_json::json!({
+        "options": ["option name Hash type spin default 16 min 1 max 1024"],
+        "default": quiet_default()
+    }));
+    fixture.write_epd(&[START]);
+
+    let output = fixture.run(&["--hash", "64"]);
+    assert!(output.status.success());
+    assert_eq!(fixture.csv_rows().len(), 1);
+}
+
+#[test]
+fn missing_uciok_fails_the_handshake() {
+    let fixture = Fixture::new(serde_json::json!({ "uciok": false }));
+    fixture.write_epd(&[START]);
+
+    let output = fixture.run(&[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no uciok"));
+    assert!(!fixture.path("results.csv").exists());
+}
+
+#[test]
+fn hanging_handshake_times_out() {
+    let fixture = Fixture::new(serde_json::json!({ "hang_in_handshake": true }));
+    fixture.write_epd(&[START]);
+
+    let output = fixture.run(&["--handshakeTimeout", "0.3"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no uciok"));
+    assert!(!fixture.path("results.csv").exists());
+}
+
+#[test]
+fn garbage_lines_are_skipped() {
+    let fixture = Fixture::new(serde_json::json!({
+        "positions": [mate_in_1("normal", &[
+            "info depth three score mate 1",
+            "info depth 2 score banana 7",
+            "this is not uci at all",
+            "info depth 3 score mate 1 nodes 100 pv g6g7",
+            "bestmove g6g7"
+        ])]
+    }));
+    fixture.write_epd(&[&format!("{MATE_IN_1} bm #1;")]);
+
+    let output = fixture.run(&[]);
+    assert!(output.status.success());
+    let all = &fixture.csv_rows()[0];
+    assert_eq!(all["best_mates"], "1");
+    assert_eq!(all["max_nodes"], "100");
+}
+
+#[test]
+fn bound_mate_scores_are_not_counted_as_found() {
+    let fixture = Fixture::new(serde_json::json!({
+        "positions": [mate_in_1("normal", &[
+            "info depth 3 score mate 1 lowerbound nodes 100 pv g6g7",
+            "bestmove g6g7"
+        ])]
+    }));
+    fixture.write_epd(&[&format!("{MATE_IN_1} bm #1;")]);
+
+    assert!(fixture.run(&[]).status.success());
+    let all = &fixture.csv_rows()[0];
+    assert_eq!(all["bound_scores"], "1");
+    assert_eq!(all["found_mates"], "0");
+    assert_eq!(all["mates_not_found"], "1");
+}
+
+#[test]
+fn illegal_and_non_mating_pvs_are_flagged() {
+    let fixture = Fixture::new(serde_json::json!({
+        "positions": [
+            mate_in_1("normal", &["info depth 3 score mate 1 pv h1h2", "bestmove h1h2"]),
+            {
+                "fen": KINGS,
+                "output": ["info depth 3 score mate 1 pv a1a2", "bestmove a1a2"]
+            }
+        ]
+    }));
+    fixture.write_epd(&[&format!("{MATE_IN_1} bm #1;"), KINGS]);
+
+    let output = fixture.run(&[]);
+    assert!(output.status.success());
+    let report = stdout(&output);
+    assert!(report.contains("Mate scores with bad PVs:"));
+    let all = &fixture.csv_rows()[0];
+    assert_eq!(all["illegal_pvs"], "1");
+    assert_eq!(all["bad_mate_pvs"], "2");
+}
+
+#[test]
+fn hanging_engine_is_stopped_and_the_run_continues() {
+    let fixture = Fixture::new(serde_json::json!({
+        "positions": [
+            mate_in_1("hang_until_stop", &["info depth 1 score cp 500 nodes 10 pv g6g7"]),
+            { "fen": KINGS, "behavior": "hang", "output": [] }
+        ],
+        "default": quiet_default()
+    }));
+    fixture.write_epd(&[MATE_IN_1, KINGS, START]);
+
+    let output = fixture.run(&["--timeout", "0.3", "--timeoutGrace", "0.3"]);
+    assert!(output.status.success());
+    let report = stdout(&output);
+    assert!(report.contains("Timed out positions:"));
+    let all = &fixture.csv_rows()[0];
+    assert_eq!(all["positions_analyzed"], "1");
+    assert_eq!(all["timed_out"], "2");
+    assert_eq!(all["crashed"], "0");
+    // The engine was restarted after the hard hang and searched the last one
+    assert_eq!(all["max_nodes"], "2000");
+}
+
+#[test]
+fn crashing_engine_is_reported_and_restarted() {
+    let fixture = Fixture::new(serde_json::json!({
+        "positions": [
+            mate_in_1("crash", &["info depth 1 score cp 500 nodes 10 pv g6g7"]),
+            { "fen": KINGS, "behavior": "exit", "output": ["info string giving up"] }
+        ],
+        "default": quiet_default()
+    }));
+    fixture.write_epd(&[MATE_IN_1, KINGS, START]);
+
+    let output = fixture.run(&[]);
+    assert!(output.status.success());
+    let report = stdout(&output);
+    assert!(report.contains("Engine crashes:"));
+    assert!(report.contains("< info string giving up"));
+    let all = &fixture.csv_rows()[0];
+    assert_eq!(all["positions_analyzed"], "1");
+    assert_eq!(all["crashed"], "2");
+    assert_eq!(all["max_nodes"], "2000");
+}
+
+#[test]
+fn parallel_workers_match_a_single_engine() {
+    let fixture = Fixture::new(serde_json::json!({
+        "positions": [
+            mate_in_1("normal", &["info depth 3 score mate 1 nodes 100 pv g6g7", "bestmove g6g7"]),
+            { "fen": KINGS, "behavior": "crash", "output": [] }
+        ],
+        "default": quiet_default()
+    }));
+    fixture.write_epd(&[
+        &format!("{MATE_IN_1} bm #1;"),
+        KINGS,
+        START,
+        MATE_IN_1,
+        START,
+    ]);
+
+    assert!(fixture.run(&[]).status.success());
+    assert!(fixture.run(&["--concurrency", "3"]).status.success());
+    let rows = fixture.csv_rows();
+    let (single, parallel) = (&rows[0], &rows[1]);
+    for column in [
+        "positions_analyzed",
+        "avg_nodes",
+        "best_mates",
+        "total_mates",
+        "crashed",
+    ] {
+        assert_eq!(single[column], parallel[column], "{column}");
+    }
+}
+
+#[test]
+fn replay_reproduces_the_logged_run() {
+    let fixture = Fixture::new(serde_json::json!({
+        "positions": [
+            mate_in_1("normal", &["info depth 3 score mate 1 nodes 100 pv g6g7", "bestmove g6g7"]),
+            { "fen": KINGS, "behavior": "hang", "output": [] }
+        ],
+        "default": quiet_default()
+    }));
+    fixture.write_epd(&[&format!("{MATE_IN_1} bm #1;"), KINGS, START]);
+
+    let log = fixture.path("run.log");
+    let live = fixture.run(&[
+        "--timeout",
+        "0.3",
+        "--timeoutGrace",
+        "0.1",
+        "--logFile",
+        log.to_str().unwrap(),
+    ]);
+    assert!(live.status.success());
+
+    let replayed_csv = fixture.path("replayed.csv");
+    let replayed = Command::new(env!("CARGO_BIN_EXE_engine_mate_stats"))
+        .arg("replay")
+        .arg("--log")
+        .arg(&log)
+        .arg("-o")
+        .arg(&replayed_csv)
+        .output()
+        .unwrap();
+    assert!(replayed.status.success());
+    assert_eq!(fixture.csv_rows(), read_csv(&replayed_csv));
+}
+
+#[test]
+fn time_limits_are_sent_in_whole_milliseconds() {
+    let fixture = Fixture::new(serde_json::json!({ "default": quiet_default() }));
+    fixture.write_epd(&[START]);
+
+    for args in [
+        &["--time", "0.0004"][..],
+        &["--time", "0"],
+        &["--time", "1e30"],
+        &["--movetime", "0"],
+        &["--time", "1", "--movetime", "1000"],
+    ] {
+        let output = fixture.run(args);
+        assert!(!output.stderr.is_empty(), "{args:?}");
+        assert!(!fixture.path("results.csv").exists(), "{args:?}");
+    }
+
+    assert!(fixture.run(&["--time", "0.2504"]).status.success());
+    assert!(fixture.run(&["--movetime", "300"]).status.success());
+    let rows = fixture.csv_rows();
+    assert_eq!(rows[0]["limit_movetime_ms"], "250");
+    assert_eq!(rows[0]["limit_depth"], "");
+    assert_eq!(rows[1]["limit_movetime_ms"], "300");
+}
+
+#[test]
+fn non_positive_timeouts_are_rejected() {
+    let fixture = Fixture::new(serde_json::json!({ "default": quiet_default() }));
+    fixture.write_epd(&[START]);
+
+    for arg in [
+        "--timeout=-1",
+        "--timeout=0",
+        "--timeoutGrace=-1",
+        "--timeoutGrace=0",
+        "--timeout=NaN",
+    ] {
+        let output = fixture.run(&[arg]);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("seconds > 0"));
+        assert!(!fixture.path("results.csv").exists(), "{arg}");
+    }
+
+    let log = fixture.path("run.log");
+    assert!(
+        fixture
+            .run(&["--logFile", log.to_str().unwrap()])
+            .status
+            .success()
+    );
+    let transcript = std::fs::read_to_string(&log).unwrap();
+    for grace in ["-1.0", "0.0"] {
+        std::fs::write(
+            &log,
+            transcript.replace("\"grace_secs\":1.0", &format!("\"grace_secs\":{grace}")),
+        )
+        .unwrap();
+        let replayed = Command::new(env!("CARGO_BIN_EXE_engine_mate_stats"))
+            .arg("replay")
+            .arg("--log")
+            .arg(&log)
+            .arg("-o")
+            .arg(fixture.path("replayed.csv"))
+            .output()
+            .unwrap();
+        assert!(!replayed.status.success(), "{grace}");
+        assert!(String::from_utf8_lossy(&replayed.stderr).contains("invalid timeout"));
+    }
+}
+
+#[test]
+fn json_and_jsonl_exports_hold_every_position() {
+    let fixture = Fixture::new(serde_json::json!({
+        "name": "Mock JSON",
+        "positions": [
+            mate_in_1("normal", &[
+                "info depth 3 seldepth 4 score mate 1 nodes 100 nps 5000 time 20 pv g6g7",
+                "bestmove g6g7"
+            ]),
+            { "fen": KINGS, "behavior": "crash", "output": ["info depth 1 score cp 0 lowerbound"] }
+        ],
+        "default": quiet_default()
+    }));
+    fixture.write_epd(&[&format!("{MATE_IN_1} bm #1 Qg7#;"), KINGS, START]);
+    let json = fixture.path("run.json");
+    let jsonl = fixture.path("run.jsonl");
+
+    let output = fixture.run(&[
+        "--concurrency",
+        "2",
+        "--jsonOut",
+        json.to_str().unwrap(),
+        "--jsonlOut",
+        jsonl.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+
+    let document: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&json).unwrap()).unwrap();
+    assert_eq!(document["report"]["engine_name"], "Mock JSON");
+    assert_eq!(document["config"]["threads"], 1);
+    assert_eq!(document["report"]["overall"]["crashed"], 1);
+    let positions = document["positions"].as_array().unwrap();
+    assert_eq!(positions.len(), 3);
+
+    let mate = &positions[0];
+    assert_eq!(mate["index"], 1);
+    assert_eq!(mate["fen"], format!("{MATE_IN_1} 0 1"));
+    assert_eq!(mate["expected_mate"], 1);
+    assert_eq!(mate["score_mate"], 1);
+    assert_eq!(mate["score_bound"], "exact");
+    assert_eq!(mate["bound"], false);
+    assert_eq!(mate["pv"], serde_json::json!(["g6g7"]));
+    assert_eq!(mate["seldepth"], 4);
+    assert_eq!(mate["mate_class"], "best");
+    assert_eq!(mate["first_move_hit"], true);
+    assert_eq!(mate["pv_issue"], serde_json::Value::Null);
+    assert_eq!(mate["status"], "completed");
+
+    let crash = &positions[1];
+    assert_eq!(crash["status"], "crashed");
+    assert_eq!(crash["bound"], true);
+    assert!(crash["exit_status"].is_string());
+
+    // Streamed in completion order, so compare by index
+    let mut streamed: Vec<serde_json::Value> = std::fs::read_to_string(&jsonl)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    streamed.sort_by_key(|record| record["index"].as_u64());
+    assert_eq!(&streamed, positions);
+}
+
+#[test]
+fn resume_skips_positions_in_the_checkpoint() {
+    let fixture = Fixture::new(serde_json::json!({
+        "positions": [
+            mate_in_1("normal", &["info depth 3 score mate 1 nodes 100 pv g6g7", "bestmove g6g7"]),
+        ],
+        "default": quiet_default()
+    }));
+    fixture.write_epd(&[&format!("{MATE_IN_1} bm #1;"), KINGS, START]);
+    let checkpoint = fixture.path("run.ckpt");
+    let checkpoint_arg = checkpoint.to_str().unwrap();
+
+    assert!(
+        fixture
+            .run(&["--checkpoint", checkpoint_arg])
+            .status
+            .success()
+    );
+    let complete = fixture.csv_rows().remove(0);
+
+    // Starting over would silently discard the checkpoint
+    let output = fixture.run(&["--checkpoint", checkpoint_arg]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--resume"));
+
+    // Keep the header and the first position, with the second cut off
+    // mid-write as if the run had been killed
+    let contents = std::fs::read_to_string(&checkpoint).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 4);
+    std::fs::write(
+        &checkpoint,
+        format!("{}\n{}\n{}", lines[0], lines[1], &lines[2][..20]),
+    )
+    .unwrap();
+
+    // The mate position would now crash the engine if it were searched again
+    let script = serde_json::json!({
+        "positions": [mate_in_1("crash", &[])],
+        "default": quiet_default()
+    });
+    std::fs::write(fixture.path("script.json"), script.to_string()).unwrap();
+    let output = fixture.run(&["--checkpoint", checkpoint_arg, "--resume"]);
+    assert!(output.status.success());
+    let resumed = fixture.csv_rows().remove(1);
+    for column in [
+        "positions_analyzed",
+        "best_mates",
+        "avg_nodes",
+        "crashed",
+        "mates in 1",
+    ] {
+        assert_eq!(resumed[column], complete[column], "{column}");
+    }
+    let records = std::fs::read_to_string(&checkpoint).unwrap();
+    // Header, the kept position and the two searched again; the cut-off
+    // record stays behind on a line of its own
+    assert_eq!(records.lines().filter(|l| l.ends_with('}')).count(), 4);
+}
+
+#[test]
+fn resume_refuses_a_different_engine_or_limits() {
+    let fixture = Fixture::new(serde_json::json!({ "default": quiet_default() }));
+    fixture.write_epd(&[START, KINGS]);
+    let checkpoint = fixture.path("run.ckpt");
+    let checkpoint_arg = checkpoint.to_str().unwrap();
+    assert!(
+        fixture
+            .run(&["--checkpoint", checkpoint_arg, "--depth", "5"])
+            .status
+            .success()
+    );
+
+    let output = fixture.run(&["--checkpoint", checkpoint_arg, "--resume", "--depth", "6"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("search limits"));
+
+    let output = fixture.run(&[
+        "--checkpoint",
+        checkpoint_arg,
+        "--resume",
+        "--depth",
+        "5",
+        "--clearHash",
+        "--newGamePerPosition",
+        "false",
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--clearHash"));
+    assert!(stderr.contains("ucinewgame"));
+
+    // Same engine, but a different build
+    let rebuilt = fixture.path("mock_rebuilt");
+    std::fs::copy(env!("CARGO_BIN_EXE_mock_uci_engine"), &rebuilt).unwrap();
+    let mut binary = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&rebuilt)
+        .unwrap();
+    std::io::Write::write_all(&mut binary, b"rebuilt").unwrap();
+    drop(binary);
+    let output = fixture.run_engine(
+        &rebuilt,
+        &["--checkpoint", checkpoint_arg, "--resume", "--depth", "5"],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("engine binary"));
+
+    let output = fixture.run(&[
+        "--checkpoint",
+        checkpoint_arg,
+        "--resume",
+        "--forceResume",
+        "--depth",
+        "6",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(fixture.csv_rows()[0]["positions_analyzed"], "2");
+}
+
+fn cache_command(fixture: &Fixture, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_engine_mate_stats"))
+        .arg("cache")
+        .args(args)
+        .arg("--cacheDir")
+        .arg(fixture.path("cache"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    stdout(&output)
+}
+
+#[test]
+fn cached_results_are_not_searched_again() {
+    let fixture = Fixture::new(serde_json::json!({
+        "positions": [
+            mate_in_1("normal", &["info depth 3 score mate 1 nodes 100 pv g6g7", "bestmove g6g7"]),
+            { "fen": KINGS, "behavior": "hang", "output": [] }
+        ],
+        "default": quiet_default()
+    }));
+    fixture.write_epd(&[&format!("{MATE_IN_1} bm #1;"), KINGS, START]);
+    let timeout = ["--timeout", "0.2", "--timeoutGrace", "0.1"];
+    assert!(fixture.run(&timeout).status.success());
+    assert!(cache_command(&fixture, &["stats"]).contains("Entries:         2"));
+
+    // Searching the mate position again would now crash the engine
+    let script = serde_json::json!({
+        "positions": [mate_in_1("crash", &[])],
+        "default": quiet_default()
+    });
+    std::fs::write(fixture.path("script.json"), script.to_string()).unwrap();
+
+    assert!(fixture.run(&timeout).status.success());
+    let cached = fixture.csv_rows().remove(1);
+    assert_eq!(cached["best_mates"], "1");
+    assert_eq!(cached["crashed"], "0");
+    // The timeout was not cached, so that position was searched again
+    assert_eq!(cached["timed_out"], "0");
+
+    let mut args = timeout.to_vec();
+    args.push("--noCache");
+    assert!(fixture.run(&args).status.success());
+    assert_eq!(fixture.csv_rows()[2]["crashed"], "1");
+
+    // Different limits are a different key
+    assert!(fixture.run(&["--depth", "3"]).status.success());
+    assert_eq!(fixture.csv_rows()[3]["crashed"], "1");
+    // So is clearing the hash before every position
+    assert!(fixture.run(&["--clearHash"]).status.success());
+    assert_eq!(fixture.csv_rows()[4]["crashed"], "1");
+
+    assert!(cache_command(&fixture, &["prune", "--olderThan", "1"]).contains("Removed 0"));
+    // Ages that are not a number of days must not empty the cache
+    for age in ["-1", "NaN", "inf"] {
+        let arg = format!("--olderThan={age}");
+        assert!(!cache_command(&fixture, &["prune", &arg]).contains("Removed"));
+    }
+    // Three positions at the default settings, two each at depth 3 and
+    // with --clearHash
+    assert!(cache_command(&fixture, &["prune", "--all"]).contains("Removed 7"));
+    assert!(cache_command(&fixture, &["stats"]).contains("Entries:         0"));
+}
+
+#[test]
+fn replay_includes_cached_and_resumed_results() {
+    let fixture = Fixture::new(serde_json::json!({
+        "positions": [
+            mate_in_1("normal", &["info depth 3 score mate 1 nodes 100 pv g6g7", "bestmove g6g7"])
+        ],
+        "default": quiet_default()
+    }));
+    fixture.write_epd(&[&format!("{MATE_IN_1} bm #1;"), KINGS, START]);
+    let checkpoint = fixture.path("run.ckpt");
+    let checkpoint_arg = checkpoint.to_str().unwrap();
+    // Fills both the cache and the checkpoint
+    assert!(
+        fixture
+            .run(&["--checkpoint", checkpoint_arg])
+            .status
+            .success()
+    );
+
+    let replay_matches_last_run = |log: &Path, csv_name: &str| {
+        let live = fixture.csv_rows().pop().unwrap();
+        assert_eq!(live["positions_analyzed"], "3");
+        let replayed_csv = fixture.path(csv_name);
+        let replayed = Command::new(env!("CARGO_BIN_EXE_engine_mate_stats"))
+            .arg("replay")
+            .arg("--log")
+            .arg(log)
+            .arg("-o")
+            .arg(&replayed_csv)
+            .output()
+            .unwrap();
+        assert!(replayed.status.success());
+        assert_eq!(vec![live], read_csv(&replayed_csv));
+    };
+
+    let cached_log = fixture.path("cached.log");
+    let output = fixture.run(&["--logFile", cached_log.to_str().unwrap()]);
+    assert!(output.status.success());
+    replay_matches_last_run(&cached_log, "cached.csv");
+
+    let resumed_log = fixture.path("resumed.log");
+    let output = fixture.run(&[
+        "--noCache",
+        "--checkpoint",
+        checkpoint_arg,
+        "--resume",
+        "--logFile",
+        resumed_log.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    replay_matches_last_run(&resumed_log, "resumed.csv");
+}
+
+#[test]
+fn unreadable_suites_fail_the_run() {
+    let fixture = Fixture::new(serde_json::json!({ "default": quiet_default() }));
+
+    let output = fixture.run(&[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        stderr.matches("Unable to read EPD file").count(),
+        1,
+        "{stderr}"
+    );
+
+    fixture.write_epd(&[START]);
+    let pattern = fixture.path("missing-*.epd");
+    let output = fixture.run(&["--epdFile", pattern.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No files match"));
+    assert!(!fixture.path("results.csv").exists());
+}
+
+#[test]
+fn invalid_positions_fail_the_run_unless_skipped() {
+    let fixture = Fixture::new(serde_json::json!({ "default": quiet_default() }));
+    fixture.write_epd(&[START, "7k/6Q1/5K2/8/8/8/8/8 w - -", KINGS]);
+
+    let output = fixture.run(&[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("suite.epd:2: invalid position"), "{stderr}");
+    assert!(!fixture.path("results.csv").exists());
+
+    let output = fixture.run(&["--skipInvalid"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("Skipping"));
+    assert_eq!(fixture.csv_rows()[0]["positions_analyzed"], "2");
+}
+
+#[test]
+fn suite_dedup_writes_the_cleaned_suite() {
+    let fixture = Fixture::new(serde_json::json!({}));
+    let suite = fixture.write_epd(&[
+        "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1;",
+        START,
+        "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1; hmvc 4; fmvn 9;",
+        KINGS,
+        START,
+    ]);
+    for (name, expected) in [
+        (
+            "clean.epd",
+            "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1;\n\
+             rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -\n\
+             8/8/8/8/8/8/8/K6k w - -\n",
+        ),
+        (
+            "clean.json",
+            "{\n  \"fens\": [\n\
+             \x20   \"7k/5K2/6Q1/8/8/8/8/8 w - - 0 1\",\n\
+             \x20   \"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\",\n\
+             \x20   \"8/8/8/8/8/8/8/K6k w - - 0 1\"\n  ]\n}\n",
+        ),
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_engine_mate_stats"))
+            .args(["suite", "dedup"])
+            .arg(&suite)
+            .arg("-o")
+            .arg(fixture.path(name))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert!(stdout(&output).contains("Dropped 2 duplicate positions"));
+        assert_eq!(
+            std::fs::read_to_string(fixture.path(name)).unwrap(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn positions_before_mate_in_pgn_games_are_analyzed() {
+    let fixture = Fixture::new(serde_json::json!({
+        "positions": [mate_in_1("normal", &[
+            "info depth 3 seldepth 3 score mate 1 nodes 100 nps 10000 time 10 pv g6g7",
+            "bestmove g6g7"
+        ])],
+        "default": quiet_default()
+    }));
+    fixture.write_epd(&[]);
+    let games = fixture.path("games.pgn");
+    std::fs::write(
+        &games,
+        format!("[FEN \"{MATE_IN_1} 0 1\"]\n\n1. Qg7# 1-0\n\n[Event \"?\"]\n\n1. e4 *\n"),
+    )
+    .unwrap();
+
+    let output = fixture.run(&["--pgnFile", games.to_str().unwrap(), "--beforeMate", "1"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    let all = &fixture.csv_rows()[0];
+    assert_eq!(all["positions_analyzed"], "1");
+    assert_eq!(all["expected_mates"], "1");
+    assert_eq!(all["best_mates"], "1");
+}
+
+#[test]
+fn solver_annotates_positions_and_checks_mate_scores() {
+    let fixture = Fixture::new(serde_json::json!({
+        "positions": [
+            mate_in_1("normal", &[
+                "info depth 3 seldepth 3 score mate 1 nodes 100 nps 10000 time 10 pv g6g7",
+                "bestmove g6g7"
+            ]),
+            { "fen": KINGS, "behavior": "normal", "output": [
+                "info depth 3 seldepth 3 score mate 2 nodes 100 nps 10000 time 10 pv a1b2",
+                "bestmove a1b2"
+            ] }
+        ],
+        "default": quiet_default()
+    }));
+    // No annotations: the solver supplies the mate in 1
+    fixture.write_epd(&[MATE_IN_1, KINGS, START]);
+
+    let output = fixture.run(&["--solveMates", "2"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(stdout(&output).contains("Mate scores refuted by the solver:"));
+    let all = &fixture.csv_rows()[0];
+    assert_eq!(all["expected_mates"], "1");
+    assert_eq!(all["best_mates"], "1");
+    assert_eq!(all["mates_proven"], "1");
+    assert_eq!(all["mates_refuted"], "1");
+}
@@ -0,0 +1,139 @@
+//! Reading PGN games and taking suite positions from them.
+
+use engine_mate_stats::fens::{Fens, SkipReason};
+use engine_mate_stats::pgn::{PgnExtract, PgnReader, extract_positions};
+
+const GAMES: &str = r#"[Event "Fool's mate"]
+[Site "?"]
+
+1. f3 e5 2. g4 $4 (2. e4 Qh4+ 3. g3) 2... Qh4# 0-1
+
+[Event "Annotated"]
+[White "A \"quoted\" name"]
+
+1. e4 { [%eval 0.3] } 1... e5 2. Bc4 Nc6 3. Qh5 Nf6?? { [%eval #1]
+[%clk 0:01:00] } 4. Qxf7# 1-0
+
+[Event "Composed"]
+[SetUp "1"]
+[FEN "7k/5K2/6Q1/8/8/8/8/8 w - - 0 1"]
+
+{#1} 1. Qg7# *
+"#;
+
+fn games() -> Vec<engine_mate_stats::pgn::PgnGame> {
+    PgnReader::new(GAMES.as_bytes())
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+fn extract(extract: PgnExtract) -> Vec<Vec<(String, Option<i32>)>> {
+    games()
+        .iter()
+        .map(|game| {
+            extract_positions(game, "games.pgn", &extract)
+                .into_iter()
+                .map(|p| {
+                    let p = p.unwrap();
+                    (p.fen, p.expected_mate)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn games_are_split_with_their_tags() {
+    let games = games();
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[0].line, 1);
+    assert_eq!(games[1].tag("White"), Some("A \"quoted\" name"));
+    // The tag-like line inside the comment stays part of the movetext
+    assert!(games[1].movetext.contains("[%clk 0:01:00]"));
+    assert_eq!(games[2].line, 12);
+}
+
+#[test]
+fn positions_at_plies_skip_variations_and_nags() {
+    let found = extract(PgnExtract {
+        plies: vec![3, 40],
+        ..PgnExtract::default()
+    });
+    assert_eq!(
+        found[0],
+        [(
+            "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2".to_string(),
+            None
+        )]
+    );
+    assert_eq!(found[1].len(), 1);
+    assert!(found[2].is_empty());
+}
+
+#[test]
+fn announced_mates_are_taken_from_the_side_to_move() {
+    let found = extract(PgnExtract {
+        mate_annotations: true,
+        ..PgnExtract::default()
+    });
+    assert!(found[0].is_empty());
+    // `#1` for White after Black's blunder, White to move
+    assert_eq!(found[1].len(), 1);
+    assert_eq!(found[1][0].1, Some(1));
+    assert_eq!(
+        found[1][0].0,
+        "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4"
+    );
+    assert_eq!(
+        found[2],
+        [("7k/5K2/6Q1/8/8/8/8/8 w - - 0 1".to_string(), Some(1))]
+    );
+}
+
+#[test]
+fn positions_before_mate_carry_the_remaining_distance() {
+    let found = extract(PgnExtract {
+        before_mate: vec![1, 2],
+        ..PgnExtract::default()
+    });
+    let mates: Vec<Vec<Option<i32>>> = found
+        .iter()
+        .map(|game| game.iter().map(|(_, mate)| *mate).collect())
+        .collect();
+    assert_eq!(
+        mates,
+        [
+            vec![Some(-1), Some(1)],
+            vec![Some(-1), Some(1)],
+            vec![Some(1)]
+        ]
+    );
+}
+
+#[test]
+fn illegal_moves_are_reported_per_game() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("games.pgn");
+    std::fs::write(
+        &path,
+        format!("{GAMES}\n[Event \"Broken\"]\n\n1. e4 e5 2. Ke3 *\n"),
+    )
+    .unwrap();
+    let fens = Fens::load_pgn(
+        path.to_str().unwrap(),
+        &PgnExtract {
+            before_mate: vec![1],
+            plies: vec![2],
+            ..PgnExtract::default()
+        },
+    )
+    .unwrap();
+    let skipped: Vec<_> = fens.skipped().iter().map(|p| p.index).collect();
+    assert_eq!(skipped, [4]);
+    assert!(
+        matches!(&fens.skipped()[0].reason, SkipReason::Invalid(r) if r.contains("'Ke3' at ply 3"))
+    );
+    // Two positions from each of the first two games, one from the composed
+    // game and ply 2 of the broken one
+    assert_eq!(fens.positions().len(), 6);
+}
@@ -0,0 +1,360 @@
+//! The console, CSV and JSON outputs must report the same numbers.
+
+use engine_mate_stats::export::RunDocument;
+use engine_mate_stats::report::MATE_COLUMNS;
+use engine_mate_stats::{
+    Analyzer, ConsoleRenderer, CsvRenderer, EngineResult, InfoLine, MateRun, Renderer, Report,
+    ResultStatus, SearchLimits, epd, pv_check,
+};
+use std::collections::HashMap;
+
+fn result(epd_line: &str, source: &str, info: &str) -> EngineResult {
+    let position = epd::parse_epd_line(epd_line, source).unwrap();
+    let mut result = EngineResult::new(&position);
+    result.apply_info(&InfoLine::parse(info).unwrap());
+    result.pv_issue = pv_check::verify_pv(&result.fen, &result.pv, result.mate());
+    result.bestmove = result.pv.first().cloned().unwrap_or_default();
+    result
+}
+
+fn report() -> Report {
+    let mut analyzer = Analyzer::new();
+    for (index, mut res) in [
+        result(
+            "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1; pv g6g7;",
+            "a.epd",
+            "info depth 3 seldepth 3 score mate 1 nodes 120 nps 60000 time 2 pv g6g7",
+        ),
+        result(
+            "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1 Qg7#;",
+            "a.epd",
+            "info depth 5 score mate 3 nodes 5000 nps 1000000 time 5 pv g6g5",
+        ),
+        result(
+            "6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#;",
+            "b.epd",
+            "info depth 7 score cp 300 lowerbound nodes 30000 nps 2000000 time 15 pv a1a8",
+        ),
+        result(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+            "b.epd",
+            "info depth 9 score mate 25 nodes 800000 nps 4000000 time 200 pv e2e5",
+        ),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        // Solver verdicts, set by hand
+        res.mate_verified = match index {
+            0 => Some(true),
+            1 => Some(false),
+            _ => None,
+        };
+        analyzer.add_result(res);
+    }
+    let limits = SearchLimits::new(Some(1_000_000), None, None, None);
+    analyzer.report("Mock", "engine_mate_stats -n 1000000", &limits, "first 10")
+}
+
+/// First number after `label` on the console line that starts with it.
+fn console_value(console: &str, label: &str) -> String {
+    let line = console
+        .lines()
+        .find(|line| line.trim_start().starts_with(label))
+        .unwrap_or_else(|| panic!("no console line for {label}"));
+    line.trim_start()[label.len()..]
+        .split_whitespace()
+        .next()
+        .unwrap()
+        .trim_end_matches(['%', 'M'])
+        .to_string()
+}
+
+#[test]
+fn console_and_csv_report_the_same_numbers() {
+    let report = report();
+    let mut console = ConsoleRenderer::new(Vec::new());
+    console.render(&report).unwrap();
+    let console = String::from_utf8(console.into_inner()).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let csv_path = dir.path().join("results.csv");
+    CsvRenderer::new(csv_path.to_str().unwrap())
+        .render(&report)
+        .unwrap();
+    let mut reader = csv::Reader::from_path(&csv_path).unwrap();
+    let headers = reader.headers().unwrap().clone();
+    let rows: Vec<HashMap<String, String>> = reader
+        .records()
+        .map(|r| {
+            headers
+                .iter()
+                .zip(r.unwrap().iter())
+                .map(|(h, v)| (h.to_string(), v.to_string()))
+                .collect()
+        })
+        .collect();
+    assert_eq!(rows.len(), 3);
+    let all = &rows[0];
+    assert_eq!(all["source"], "all");
+    assert_eq!(all["first_move_hits"], "67");
+    assert_eq!(all["selection"], "first 10");
+    assert_eq!(all["mates_proven"], "1");
+    assert_eq!(all["mates_refuted"], "1");
+    assert!(console.contains("Positions: first 10"));
+
+    for (label, column) in [
+        ("Positions analyzed:", "positions_analyzed"),
+        ("Average nodes per search:", "avg_nodes"),
+        ("Average depth per search:", "avg_depth"),
+        ("Average effective branching factor:", "avg_ebf"),
+        ("Average NPS:", "avg_nps"),
+        ("Average time per search (ms):", "avg_time_ms"),
+        ("Peak NPS:", "peak_nps"),
+        ("Node StdDev:", "node_stddev"),
+        ("Max Node Outlier:", "max_nodes"),
+        ("Min Node Speed:", "min_nodes"),
+        ("Mates Found:", "total_mates"),
+        ("Known Mates:", "expected_mates"),
+        ("Found mates:", "found_mates"),
+        ("Longer mates:", "longer_mates"),
+        ("Impossible mates:", "impossible_mates"),
+        ("Wrong sign mates:", "wrong_sign_mates"),
+        ("No mate found:", "mates_not_found"),
+        ("Bad Mate PVs:", "bad_mate_pvs"),
+        ("Mates Proven:", "mates_proven"),
+        ("Mates Refuted:", "mates_refuted"),
+        ("Illegal PVs:", "illegal_pvs"),
+        ("Bound Scores:", "bound_scores"),
+        ("Timed Out:", "timed_out"),
+        ("Crashed:", "crashed"),
+        ("First Move Hits:", "first_move_hits"),
+    ] {
+        assert_eq!(console_value(&console, label), all[column], "{label}");
+    }
+    for n in 1..=MATE_COLUMNS {
+        let column = format!("mates in {n}");
+        let label = format!("Mate in {n:<2}:");
+        if all[&column] != "0" {
+            assert_eq!(console_value(&console, &label), all[&column], "{label}");
+        }
+    }
+    // The console lists every distance; the CSV sums the long ones
+    let longer: u64 = (MATE_COLUMNS + 1..=25)
+        .map(|n| {
+            console_value(&console, &format!("Mate in {n:<2}:"))
+                .parse::<u64>()
+                .unwrap()
+        })
+        .sum();
+    assert_eq!(longer, 1);
+    assert_eq!(
+        all[&format!("mates in >{MATE_COLUMNS}")],
+        longer.to_string()
+    );
+
+    for (suite, row) in report.suites.iter().zip(&rows[1..]) {
+        assert_eq!(row["source"], suite.source);
+        let line = console
+            .lines()
+            .find(|line| line.trim_start().starts_with(&format!("{}:", suite.source)))
+            .unwrap();
+        assert!(line.contains(&format!("{} positions", row["positions_analyzed"])));
+        assert!(line.contains(&format!("found mates: {}", row["found_mates"])));
+        assert!(line.contains(&format!("best mates: {}", row["best_mates"])));
+    }
+}
+
+#[test]
+fn json_document_round_trips() {
+    let report = report();
+    let results = [result(
+        "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1;",
+        "a.epd",
+        "info depth 3 score mate 1 nodes 120 pv g6g7",
+    )];
+    let run = MateRun::new("engine").limits(report.limits);
+    let document = RunDocument::new(run.run_info(), report.clone(), &results);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("report.json");
+    document.write(path.to_str().unwrap()).unwrap();
+    let json = std::fs::read_to_string(&path).unwrap();
+    let parsed: RunDocument = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.report, report);
+    assert_eq!(parsed.report.overall.mates_in(25), 1);
+    assert_eq!(parsed.positions, document.positions);
+    assert_eq!(parsed.config.limits, report.limits);
+}
+
+#[test]
+fn first_move_hits_are_empty_without_ground_truth() {
+    let mut analyzer = Analyzer::new();
+    analyzer.add_result(result(
+        "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1;",
+        "a.epd",
+        "info depth 3 score mate 1 nodes 120 pv g6g7",
+    ));
+    let limits = SearchLimits::new(None, Some(3), None, None);
+    let report = analyzer.report("Mock", "", &limits, "");
+    assert_eq!(report.overall.first_move_hits, None);
+
+    let mut console = ConsoleRenderer::new(Vec::new());
+    console.render(&report).unwrap();
+    let console = String::from_utf8(console.into_inner()).unwrap();
+    assert!(console.contains("First Move Hits: n/a"));
+
+    let dir = tempfile::tempdir().unwrap();
+    let csv_path = dir.path().join("results.csv");
+    CsvRenderer::new(csv_path.to_str().unwrap())
+        .render(&report)
+        .unwrap();
+    let mut reader = csv::Reader::from_path(&csv_path).unwrap();
+    let column = reader
+        .headers()
+        .unwrap()
+        .iter()
+        .position(|h| h == "first_move_hits")
+        .unwrap();
+    let row = reader.records().next().unwrap().unwrap();
+    assert_eq!(&row[column], "");
+}
+
+#[test]
+fn flagged_positions_are_written_to_the_given_output() {
+    let mut analyzer = Analyzer::new();
+    analyzer.add_result(result(
+        "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1;",
+        "a.epd",
+        "info depth 5 score mate 3 nodes 5000 pv g6g5",
+    ));
+    let mut timed_out = result(
+        "8/8/8/8/8/8/8/K6k w - -",
+        "a.epd",
+        "info depth 1 score cp 0 nodes 10",
+    );
+    timed_out.status = ResultStatus::TimedOut;
+    analyzer.add_result(timed_out);
+
+    let mut out = Vec::new();
+    analyzer.write_flagged_positions(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("Mate scores with bad PVs:"));
+    assert!(out.contains("    pv g6g5"));
+    assert!(out.contains("Timed out positions:\n  8/8/8/8/8/8/8/K6k w - -"));
+    assert!(!out.contains("Engine crashes:"));
+}
+
+#[test]
+fn timed_out_positions_only_count_as_timed_out() {
+    let mut analyzer = Analyzer::new();
+    analyzer.add_result(result(
+        "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1;",
+        "a.epd",
+        "info depth 4 score mate 1 nodes 100 nps 1000 time 10 pv g6g7",
+    ));
+    let mut timed_out = result(
+        "6k1/5ppp/8/8/8/8/8/R5K1 w - - bm #1;",
+        "a.epd",
+        "info depth 1 score cp 0 upperbound nodes 9 nps 9 time 1000 pv a1a2",
+    );
+    timed_out.status = ResultStatus::TimedOut;
+    analyzer.add_result(timed_out);
+
+    let stats = analyzer.summary();
+    assert_eq!(stats.timed_out, 1);
+    assert_eq!(stats.positions_analyzed, 1);
+    assert_eq!(stats.avg_nodes, 100.0);
+    assert_eq!(stats.avg_time_ms, 10.0);
+    assert_eq!(stats.avg_depth, 4.0);
+    assert_eq!(stats.node_stddev, 0.0);
+    assert_eq!(stats.min_nodes, 100);
+    assert_eq!(stats.expected_mates, 1);
+    assert_eq!(stats.mate_classes.not_found, 0);
+    assert_eq!(stats.bound_scores, 0);
+}
+
+#[test]
+fn crashed_positions_only_count_as_crashed() {
+    let mut analyzer = Analyzer::new();
+    analyzer.add_result(result(
+        "7k/5K2/6Q1/8/8/8/8/8 w - - bm #1;",
+        "a.epd",
+        "info depth 4 score mate 1 nodes 100 nps 1000 time 10 pv g6g7",
+    ));
+    let mut crashed = result(
+        "6k1/5ppp/8/8/8/8/8/R5K1 w - - bm #1;",
+        "b.epd",
+        "info depth 2 score mate 4 nodes 7 nps 7 time 1 pv a1a2",
+    );
+    crashed.status = ResultStatus::Crashed {
+        exit_status: "signal 11".to_string(),
+        last_output: Vec::new(),
+    };
+    analyzer.add_result(crashed);
+
+    let report = analyzer.report(
+        "Mock",
+        "",
+        &SearchLimits::new(None, Some(4), None, None),
+        "",
+    );
+    let stats = &report.overall;
+    assert_eq!(stats.crashed, 1);
+    assert_eq!(stats.positions_analyzed, 1);
+    assert_eq!(stats.avg_nodes, 100.0);
+    assert_eq!(stats.min_nodes, 100);
+    assert_eq!(stats.total_mates, 1);
+    assert_eq!(stats.mates_in(4), 0);
+    assert_eq!(stats.mate_classes.longer, 0);
+    assert_eq!(stats.bad_mate_pvs, 0);
+
+    let crashed_suite = report.suites.iter().find(|s| s.source == "b.epd").unwrap();
+    assert_eq!(crashed_suite.stats.crashed, 1);
+    assert_eq!(crashed_suite.stats.positions_analyzed, 0);
+    assert_eq!(crashed_suite.stats.min_nodes, 0);
+}
+
+#[test]
+fn csv_is_not_appended_to_a_file_with_other_columns() {
+    let report = report();
+    let dir = tempfile::tempdir().unwrap();
+    let csv_path = dir.path().join("results.csv");
+    let path = csv_path.to_str().unwrap();
+
+    std::fs::write(&csv_path, "").unwrap();
+    CsvRenderer::new(path).render(&report).unwrap();
+    CsvRenderer::new(path).render(&report).unwrap();
+    let rows = csv::Reader::from_path(&csv_path).unwrap().records().count();
+    assert_eq!(rows, 6);
+
+    let old = "engine_name,cmdline,positions_analyzed\nMock,,4\n";
+    std::fs::write(&csv_path, old).unwrap();
+    let err = CsvRenderer::new(path).render(&report).unwrap_err();
+    assert!(err.to_string().contains("different columns"), "{err}");
+    assert_eq!(std::fs::read_to_string(&csv_path).unwrap(), old);
+}
+
+#[test]
+fn json_reports_without_a_selection_are_rejected() {
+    let mut json = serde_json::to_value(report()).unwrap();
+    json.as_object_mut().unwrap().remove("selection");
+    let err = serde_json::from_value::<Report>(json).unwrap_err();
+    assert!(
+        err.to_string().contains("missing field `selection`"),
+        "{err}"
+    );
+}
+
+#[test]
+fn json_reports_without_solver_counts_are_rejected() {
+    for field in ["mates_proven", "mates_refuted"] {
+        let mut json = serde_json::to_value(report()).unwrap();
+        json["overall"].as_object_mut().unwrap().remove(field);
+        let err = serde_json::from_value::<Report>(json).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(&format!("missing field `{field}`")),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Choosing which loaded positions a run analyzes.
+
+use engine_mate_stats::fens::SuitePosition;
+use engine_mate_stats::selection::{Filter, Selection};
+
+fn position(fen: &str, source: &str, expected_mate: Option<i32>) -> SuitePosition {
+    SuitePosition {
+        fen: fen.to_string(),
+        source: source.to_string(),
+        expected_mate,
+        best_moves: Vec::new(),
+    }
+}
+
+fn suite() -> Vec<SuitePosition> {
+    vec![
+        position("7k/5K2/6Q1/8/8/8/8/8 w - - 0 1", "suites/easy.epd", Some(1)),
+        position(
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+            "suites/easy.epd",
+            Some(-4),
+        ),
+        position(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "suites/hard.epd",
+            None,
+        ),
+        position("8/8/8/8/8/8/8/K6k w - - 0 1", "suites/hard.epd", Some(7)),
+    ]
+}
+
+fn select(filters: &[&str]) -> Vec<String> {
+    let selection = Selection {
+        filters: filters.iter().map(|f| f.parse().unwrap()).collect(),
+        ..Selection::first(10)
+    };
+    selection
+        .apply(suite())
+        .into_iter()
+        .map(|p| p.fen.split(' ').next().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn filters_parse_and_print_back() {
+    for text in [
+        "mate<=5",
+        "side=b",
+        "pieces>3",
+        "castling=no",
+        "source!=*hard*",
+    ] {
+        assert_eq!(text.parse::<Filter>().unwrap().to_string(), text);
+    }
+    assert_eq!(
+        "side=white".parse::<Filter>().unwrap().to_string(),
+        "side=w"
+    );
+    for bad in ["mate", "depth=3", "mate<=x", "side<w", "castling=maybe"] {
+        assert!(bad.parse::<Filter>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn filters_select_matching_positions() {
+    assert_eq!(
+        select(&["mate<=4"]),
+        ["7k/5K2/6Q1/8/8/8/8/8", "r3k2r/8/8/8/8/8/8/R3K2R"]
+    );
+    assert_eq!(select(&["side=b"]), ["r3k2r/8/8/8/8/8/8/R3K2R"]);
+    assert_eq!(select(&["pieces<=3"]).len(), 2);
+    assert_eq!(select(&["castling=yes", "source=hard.epd"]).len(), 1);
+    assert_eq!(select(&["source=suites/easy.epd", "mate>1"]).len(), 1);
+}
+
+#[test]
+fn stride_offset_and_sampling_are_deterministic() {
+    let strided = Selection {
+        offset: 1,
+        stride: 2,
+        ..Selection::first(10)
+    };
+    let picked: Vec<_> = strided
+        .apply(suite())
+        .into_iter()
+        .map(|p| p.expected_mate)
+        .collect();
+    assert_eq!(picked, [Some(-4), Some(7)]);
+    assert_eq!(strided.to_string(), "first 10, every 2, from offset 1");
+
+    let sample = |seed| Selection {
+        sample: true,
+        seed,
+        ..Selection::first(2)
+    };
+    let first = sample(7).apply(suite());
+    assert_eq!(first.len(), 2);
+    let again = sample(7).apply(suite());
+    assert_eq!(
+        first.iter().map(|p| &p.fen).collect::<Vec<_>>(),
+        again.iter().map(|p| &p.fen).collect::<Vec<_>>()
+    );
+    let order: Vec<_> = suite().into_iter().map(|p| p.fen).collect();
+    let index = |p: &SuitePosition| order.iter().position(|f| *f == p.fen).unwrap();
+    assert!(index(&first[0]) < index(&first[1]));
+    assert_eq!(sample(7).to_string(), "2 at random (seed 7)");
+}
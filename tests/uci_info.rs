@@ -0,0 +1,106 @@
+//! Parsing UCI `info` lines into `InfoLine`.
+
+use engine_mate_stats::uci_info::{InfoLine, Score, ScoreBound, ScoreValue, UciParseError};
+
+#[test]
+fn full_line_is_parsed() {
+    let info = InfoLine::parse(
+        "info depth 24 seldepth 31 multipv 1 score mate 3 wdl 1000 0 0 nodes 123456 \
+         nps 2000000 hashfull 12 tbhits 5 time 61 pv g6g7 h8g7 f7f8",
+    )
+    .unwrap();
+    assert_eq!(info.depth, Some(24));
+    assert_eq!(info.seldepth, Some(31));
+    assert_eq!(info.multipv, Some(1));
+    assert_eq!(
+        info.score,
+        Some(Score {
+            value: ScoreValue::Mate(3),
+            bound: ScoreBound::Exact
+        })
+    );
+    assert_eq!(info.wdl, Some((1000, 0, 0)));
+    assert_eq!(info.nodes, Some(123456));
+    assert_eq!(info.nps, Some(2000000));
+    assert_eq!(info.hashfull, Some(12));
+    assert_eq!(info.tbhits, Some(5));
+    assert_eq!(info.time_ms, Some(61));
+    assert_eq!(info.pv, ["g6g7", "h8g7", "f7f8"]);
+    assert!(info.unknown.is_empty());
+}
+
+#[test]
+fn score_bounds_are_recorded() {
+    let info = InfoLine::parse("info depth 10 score cp -35 upperbound nodes 10").unwrap();
+    let score = info.score.unwrap();
+    assert_eq!(score.value, ScoreValue::Cp(-35));
+    assert_eq!(score.bound, ScoreBound::Upper);
+    assert_eq!(score.exact_mate(), None);
+    assert_eq!(info.nodes, Some(10));
+
+    let score = InfoLine::parse("info score mate -2 lowerbound")
+        .unwrap()
+        .score
+        .unwrap();
+    assert_eq!(score.bound, ScoreBound::Lower);
+    assert_eq!(score.exact_mate(), None);
+    assert_eq!(score.to_string(), "mate -2 lowerbound");
+}
+
+#[test]
+fn unknown_tokens_keep_the_rest_of_the_line() {
+    let info = InfoLine::parse(
+        "info depth 12 seldepth 20 time 50 nodes 800 score mate 2 movesleft 3 pv d1h5",
+    )
+    .unwrap();
+    assert_eq!(info.depth, Some(12));
+    assert_eq!(info.nodes, Some(800));
+    assert_eq!(info.score.unwrap().exact_mate(), Some(2));
+    assert_eq!(info.pv, ["d1h5"]);
+    assert_eq!(info.unknown, ["movesleft", "3"]);
+}
+
+#[test]
+fn move_lists_and_string_consume_their_tokens() {
+    let info = InfoLine::parse("info currmove e2e4 currmovenumber 1 currline 2 e2e4 e7e5").unwrap();
+    assert_eq!(info.currmove.as_deref(), Some("e2e4"));
+    assert_eq!(info.currmovenumber, Some(1));
+    assert_eq!(info.currline, ["e2e4", "e7e5"]);
+
+    let info = InfoLine::parse("info refutation d1h5 g6h5 string NNUE evaluation enabled").unwrap();
+    assert_eq!(info.refutation, ["d1h5", "g6h5"]);
+    assert_eq!(info.string.as_deref(), Some("NNUE evaluation enabled"));
+
+    let info = InfoLine::parse("info pv e1g1 e8c8 e7e8q").unwrap();
+    assert_eq!(info.pv, ["e1g1", "e8c8", "e7e8q"]);
+}
+
+#[test]
+fn malformed_fields_are_errors() {
+    assert_eq!(
+        InfoLine::parse("bestmove e2e4"),
+        Err(UciParseError::NotInfo)
+    );
+    assert_eq!(
+        InfoLine::parse("info depth"),
+        Err(UciParseError::MissingValue("depth"))
+    );
+    assert_eq!(
+        InfoLine::parse("info nodes many"),
+        Err(UciParseError::InvalidNumber {
+            field: "nodes",
+            value: "many".to_string()
+        })
+    );
+    assert_eq!(
+        InfoLine::parse("info score wdl 1 2 3"),
+        Err(UciParseError::InvalidScore("wdl".to_string()))
+    );
+    assert_eq!(
+        InfoLine::parse("info currmove castle"),
+        Err(UciParseError::InvalidMove {
+            field: "currmove",
+            value: "castle".to_string()
+        })
+    );
+}